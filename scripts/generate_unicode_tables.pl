@@ -0,0 +1,172 @@
+#!/usr/bin/perl
+
+# Prints src/encoding/unicode/tables.rs from the Unicode database shipped with Perl:
+#   scripts/generate_unicode_tables.pl > src/encoding/unicode/tables.rs
+
+use strict;
+use warnings;
+use Unicode::UCD qw(charinfo prop_invlist prop_invmap);
+
+my $MAX_CODE_POINT = 0x10ffff;
+
+# turns an inversion map into inclusive [start, end, value] ranges, skipping $skip values
+sub ranges_from_invmap {
+    my ($property, $convert, $skip) = @_;
+    my ($list, $map) = prop_invmap($property);
+    my @ranges;
+    for my $i (0 .. $#$list) {
+        my $value = $convert->($map->[$i]);
+        next if !defined($value) || (defined($skip) && $value eq $skip);
+        my $end = $i < $#$list ? $list->[$i + 1] - 1 : $MAX_CODE_POINT;
+        push @ranges, [$list->[$i], $end, $value];
+    }
+    return merge_ranges(@ranges);
+}
+
+sub ranges_from_invlist {
+    my @list = @_;
+    my @ranges;
+    for (my $i = 0; $i < @list; $i += 2) {
+        my $end = $i + 1 < @list ? $list[$i + 1] - 1 : $MAX_CODE_POINT;
+        push @ranges, [$list[$i], $end, 1];
+    }
+    return merge_ranges(@ranges);
+}
+
+sub merge_ranges {
+    my @merged;
+    for my $range (sort { $a->[0] <=> $b->[0] } @_) {
+        if (@merged && $merged[-1][1] + 1 == $range->[0] && $merged[-1][2] eq $range->[2]) {
+            $merged[-1][1] = $range->[1];
+        } else {
+            push @merged, [@$range];
+        }
+    }
+    return @merged;
+}
+
+sub print_table {
+    my ($declaration, $items) = @_;
+    print "\npub static $declaration = &[\n";
+    my $line = "   ";
+    for my $item (@$items) {
+        if (length($line) + length($item) + 2 > 100) {
+            print "$line\n";
+            $line = "   ";
+        }
+        $line .= " $item,";
+    }
+    print "$line\n" if @$items;
+    print "];\n";
+}
+
+sub hex_code_point {
+    return sprintf("0x%x", shift);
+}
+
+sub print_ranges {
+    my ($name, $ranges) = @_;
+    my @items = map { "(" . hex_code_point($_->[0]) . ", " . hex_code_point($_->[1]) . ")" }
+        @$ranges;
+    print_table("$name: &'static [(u32, u32)]", \@items);
+}
+
+# canonical decompositions, applied recursively; Hangul syllables are decomposed algorithmically
+my %decompositions;
+{
+    my ($list, $map) = prop_invmap("Decomposition_Type");
+    for my $i (0 .. $#$list) {
+        next if $map->[$i] ne "Canonical";
+        my $end = $i < $#$list ? $list->[$i + 1] - 1 : $MAX_CODE_POINT;
+        for my $code_point ($list->[$i] .. $end) {
+            next if $code_point >= 0xac00 && $code_point <= 0xd7a3;
+            my $decomposition = charinfo($code_point)->{decomposition};
+            $decompositions{$code_point} = [map { hex } split / /, $decomposition];
+        }
+    }
+}
+
+sub full_decomposition {
+    my $code_point = shift;
+    return ($code_point) if !exists $decompositions{$code_point};
+    return map { full_decomposition($_) } @{$decompositions{$code_point}};
+}
+
+my %excluded = map { $_->[0] <= $_->[1] ? map { ($_ => 1) } $_->[0] .. $_->[1] : () }
+    ranges_from_invlist(prop_invlist("Full_Composition_Exclusion"));
+
+my @combining_classes = ranges_from_invmap("Canonical_Combining_Class", sub { shift }, "0");
+
+my %grapheme_breaks = (
+    CR => "Cr",
+    LF => "Lf",
+    Control => "Control",
+    Extend => "Extend",
+    ZWJ => "Zwj",
+    Regional_Indicator => "RegionalIndicator",
+    Prepend => "Prepend",
+    SpacingMark => "SpacingMark",
+    L => "L",
+    V => "V",
+    T => "T",
+);
+# LV and LVT syllables alternate, so they are computed instead of listed
+my @grapheme_breaks = ranges_from_invmap("Grapheme_Cluster_Break",
+                                         sub { $grapheme_breaks{$_[0]} });
+
+my @pictographic = ranges_from_invlist(prop_invlist("Extended_Pictographic"));
+
+my @wide = ranges_from_invmap("East_Asian_Width",
+                              sub { $_[0] eq "W" || $_[0] eq "F" ? 1 : undef });
+
+# nonspacing and enclosing marks, format characters except the soft hyphen, and the
+# Hangul vowels and final consonants joining a leading consonant
+my @zero_width = merge_ranges(
+    ranges_from_invmap("General_Category",
+                       sub { $_[0] =~ /^(Mn|Me|Cf)$/ ? 1 : undef }),
+    [0x1160, 0x11ff, 1],
+    [0xd7b0, 0xd7ff, 1]);
+@zero_width = map {
+    $_->[0] <= 0xad && $_->[1] >= 0xad
+        ? (($_->[0] < 0xad ? [$_->[0], 0xac, 1] : ()),
+           ($_->[1] > 0xad ? [0xae, $_->[1], 1] : ()))
+        : $_
+} @zero_width;
+
+print "// Generated by scripts/generate_unicode_tables.pl from Unicode ",
+    Unicode::UCD::UnicodeVersion(), ", do not edit\n";
+print "#![cfg_attr(rustfmt, rustfmt_skip)]\n\n";
+print "use super::segmentation::GraphemeBreak;\n";
+
+print_table("CANONICAL_COMBINING_CLASSES: &'static [(u32, u32, u8)]",
+            [map { "(" . hex_code_point($_->[0]) . ", " . hex_code_point($_->[1]) .
+                   ", $_->[2])" } @combining_classes]);
+
+my @decomposition_items;
+for my $code_point (sort { $a <=> $b } keys %decompositions) {
+    my @decomposition = full_decomposition($code_point);
+    push @decomposition_items,
+        "(" . hex_code_point($code_point) . ", &[" .
+        join(", ", map { hex_code_point($_) } @decomposition) . "])";
+}
+print_table("CANONICAL_DECOMPOSITIONS: &'static [(u32, &'static [u32])]",
+            \@decomposition_items);
+
+my @compositions;
+for my $code_point (keys %decompositions) {
+    my $decomposition = $decompositions{$code_point};
+    next if @$decomposition != 2 || $excluded{$code_point};
+    push @compositions, [@$decomposition, $code_point];
+}
+@compositions = sort { $a->[0] <=> $b->[0] || $a->[1] <=> $b->[1] } @compositions;
+print_table("CANONICAL_COMPOSITIONS: &'static [((u32, u32), u32)]",
+            [map { "((" . hex_code_point($_->[0]) . ", " . hex_code_point($_->[1]) . "), " .
+                   hex_code_point($_->[2]) . ")" } @compositions]);
+
+print_table("GRAPHEME_BREAKS: &'static [(u32, u32, GraphemeBreak)]",
+            [map { "(" . hex_code_point($_->[0]) . ", " . hex_code_point($_->[1]) .
+                   ", GraphemeBreak::$_->[2])" } @grapheme_breaks]);
+
+print_ranges("EXTENDED_PICTOGRAPHIC", \@pictographic);
+print_ranges("WIDE", \@wide);
+print_ranges("ZERO_WIDTH", \@zero_width);
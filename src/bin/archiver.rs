@@ -4,14 +4,19 @@ extern crate walkdir;
 #[macro_use]
 extern crate clap;
 
-use clap::{ArgGroup, ArgMatches, App};
+use clap::{App, Arg, ArgGroup, ArgMatches};
 use rust_experiments::encoding::bitreader::BitReader;
+use rust_experiments::encoding::arithmetic::{AdaptiveModel, ArithmeticDecoder,
+                                             ArithmeticEncoder, StaticModel};
 use rust_experiments::encoding::bitwriter::BitWriter;
+use rust_experiments::encoding::codec;
+use rust_experiments::encoding::codec::{Codec, EntropyDecoder, EntropyEncoder};
 use rust_experiments::encoding::huffman::{HuffmanEncoder, HuffmanDecoder};
 use rust_experiments::format::size_to_human_readable;
 use std::env;
 use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
 use std::mem;
 use std::path::Path;
@@ -32,34 +37,68 @@ type FileSize = u64;
 type FileEntriesLength = u64;
 type FilenameLength = u16;
 
-type Encoder<'a> = HuffmanEncoder<&'a mut File>;
-
 const CHAR_LENGTH: usize = 1;
 
-pub fn create_archive(output_filename: &str, files: Filenames) -> Result<()> {
+pub fn create_archive(output_filename: &str, files: Filenames, codec: Codec) -> Result<()> {
     let mut entries = files_to_entries(files);
 
     try!(create_parent_directories(output_filename));
-    let (header_length_bits, mut writer) = try!(write_header(output_filename, &entries));
-
-    let mut encoder = HuffmanEncoder::new(writer.get_mut(), CHAR_LENGTH);
-    try!(write_compressed_data(&mut entries, &mut encoder, header_length_bits));
+    let (header_length_bits, mut writer) = try!(write_header(output_filename, codec, &entries));
+
+    {
+        let output = writer.get_mut();
+        try!(match codec {
+            Codec::Huffman => {
+                let mut encoder = HuffmanEncoder::new(output, CHAR_LENGTH);
+                write_compressed_data(&mut entries, &mut encoder, header_length_bits)
+            }
+            Codec::Arithmetic => {
+                let mut encoder: ArithmeticEncoder<_, StaticModel> = ArithmeticEncoder::new(output);
+                write_compressed_data(&mut entries, &mut encoder, header_length_bits)
+            }
+            Codec::AdaptiveArithmetic => {
+                let mut encoder: ArithmeticEncoder<_, AdaptiveModel> =
+                    ArithmeticEncoder::new(output);
+                write_compressed_data(&mut entries, &mut encoder, header_length_bits)
+            }
+        });
+    }
 
-    try!(write_offsets(&entries, &mut encoder));
-    try!(encoder.get_output_mut().flush());
+    try!(write_offsets(&entries, &mut writer));
+    try!(writer.flush());
 
     Ok(())
 }
 
 pub fn extract_archive(input_filename: &str, files: Filenames) -> Result<()> {
-    let match_all_files = files.is_empty();
+    let (codec, entries, reader) = try!(load_header(input_filename));
+    let input = try!(reader.into_inner());
 
-    let (entries, mut reader) = try!(load_header(input_filename));
-    let mut decoder = try!(HuffmanDecoder::new(reader.get_mut()));
+    match codec {
+        Codec::Huffman => {
+            let mut decoder = try!(HuffmanDecoder::new(input));
+            extract_entries(&entries, &files, &mut decoder)
+        }
+        Codec::Arithmetic => {
+            let mut decoder: ArithmeticDecoder<_, StaticModel> =
+                try!(ArithmeticDecoder::new(input));
+            extract_entries(&entries, &files, &mut decoder)
+        }
+        Codec::AdaptiveArithmetic => {
+            let mut decoder: ArithmeticDecoder<_, AdaptiveModel> =
+                try!(ArithmeticDecoder::new(input));
+            extract_entries(&entries, &files, &mut decoder)
+        }
+    }
+}
 
+fn extract_entries<D>(entries: &FileEntries, files: &Filenames, decoder: &mut D) -> Result<()>
+    where D: EntropyDecoder
+{
+    let match_all_files = files.is_empty();
     let mut unpacked = 0;
 
-    for ref entry in &entries {
+    for entry in entries {
         // FIXME: poor performance
         let file_matched = match_all_files ||
                            files.iter()
@@ -84,7 +123,7 @@ pub fn extract_archive(input_filename: &str, files: Filenames) -> Result<()> {
 }
 
 pub fn list_archive(input_filename: &str, files: Filenames) -> Result<()> {
-    let (entries, _) = try!(load_header(input_filename));
+    let (_, entries, _) = try!(load_header(input_filename));
     let match_all_files = files.is_empty();
 
     for ref entry in &entries {
@@ -101,17 +140,61 @@ pub fn list_archive(input_filename: &str, files: Filenames) -> Result<()> {
     Ok(())
 }
 
+pub fn print_stats(files: Filenames) -> Result<()> {
+    let entries = files_to_entries(files);
+    let mut encoder = HuffmanEncoder::new(io::sink(), CHAR_LENGTH);
+
+    for entry in &entries {
+        let f = try!(File::open(entry.filename.clone()));
+        try!(encoder.analyze(f));
+    }
+    try!(encoder.analyze_finish());
+
+    let stats = encoder.stats();
+
+    println!("{:15}{:15}{}", "symbol", "weight", "code");
+    for code in &stats.codes {
+        let symbol = format!("{:?}", String::from_utf8_lossy(&code.symbol[..]));
+        println!("{:15}{:<15}{}", symbol, code.weight, code.code_to_string());
+    }
+
+    let to_bytes = |bits: f64| size_to_human_readable(bits / 8.0);
+    println!();
+    println!("symbols:        {} ({} distinct)", stats.symbols, stats.codes.len());
+    println!("input:          {}", to_bytes(stats.input_bits as f64));
+    println!("header:         {}", to_bytes(stats.header_bits as f64));
+    println!("compressed:     {}", to_bytes(stats.compressed_bits as f64));
+    println!("total:          {}", to_bytes(stats.total_bits() as f64));
+    println!("entropy bound:  {}", to_bytes(stats.entropy_bound_bits()));
+    println!("entropy:        {:.4} bits/symbol", stats.entropy);
+    println!("average code:   {:.4} bits/symbol", stats.average_code_length);
+
+    Ok(())
+}
+
 fn do_checked_main(matches: ArgMatches, files: Filenames) -> Result<()> {
     if let Some(current_directory) = matches.value_of("C") {
         try!(env::set_current_dir(current_directory));
     }
 
+    let codec_name = matches.value_of("e").unwrap_or("huffman");
+    let codec = match Codec::from_name(codec_name) {
+        Some(codec) => codec,
+        None => {
+            let e = Error::new(ErrorKind::InvalidInput,
+                               format!("unknown entropy coder '{}'", codec_name));
+            return Err(e);
+        }
+    };
+
     if let Some(output_filename) = matches.value_of("c") {
-        create_archive(output_filename, files)
+        create_archive(output_filename, files, codec)
     } else if let Some(input_filename) = matches.value_of("x") {
         extract_archive(input_filename, files)
     } else if let Some(input_filename) = matches.value_of("l") {
         list_archive(input_filename, files)
+    } else if matches.is_present("s") {
+        print_stats(files)
     } else {
         println!("{}", matches.usage());
         Ok(())
@@ -121,12 +204,19 @@ fn do_checked_main(matches: ArgMatches, files: Filenames) -> Result<()> {
 fn main() {
     let matches = App::new("Archiver")
         .args_from_usage("[FILE]... 'Filenames to compress or extract'
-                            -C <current_directory> 'Change current directory'
-                            -c <archive.huff> 'Create archive'
-                            -l <archive.huff> 'List contents'
-                            -x <archive.huff> 'Extract archive'")
-        .group(ArgGroup::with_name("cxl")
-            .args(&["c", "x", "l"])
+                            -C [current_directory] 'Change current directory'
+                            -c [archive.huff] 'Create archive'
+                            -l [archive.huff] 'List contents'
+                            -s 'Print code table and compression statistics of FILEs'
+                            -x [archive.huff] 'Extract archive'")
+        .arg(Arg::with_name("e")
+            .short("e")
+            .takes_value(true)
+            .value_name("codec")
+            .possible_values(&["huffman", "arithmetic", "adaptive"])
+            .help("Entropy coder for -c"))
+        .group(ArgGroup::with_name("cxls")
+            .args(&["c", "x", "l", "s"])
             .required(true))
         .get_matches();
 
@@ -170,11 +260,15 @@ fn files_to_entries(files: Filenames) -> FileEntries {
     entries
 }
 
-fn write_header(output_filename: &str, entries: &FileEntries) -> Result<(u64, BitWriter<File>)> {
+fn write_header(output_filename: &str,
+                codec: Codec,
+                entries: &FileEntries)
+                -> Result<(u64, BitWriter<File>)> {
     let entries_length = entries.len() as FileEntriesLength;
     let output = try!(File::create(output_filename));
     let mut writer = BitWriter::new(output);
 
+    try!(codec.write_header(&mut writer));
     try!(writer.write_u64(entries_length));
     for ref entry in entries {
         try!(writer.write_u64(entry.offset_bits));
@@ -190,10 +284,12 @@ fn write_header(output_filename: &str, entries: &FileEntries) -> Result<(u64, Bi
     Ok(result)
 }
 
-fn write_compressed_data(entries: &mut FileEntries,
-                         encoder: &mut Encoder,
-                         header_length_bits: u64)
-                         -> Result<()> {
+fn write_compressed_data<E>(entries: &mut FileEntries,
+                            encoder: &mut E,
+                            header_length_bits: u64)
+                            -> Result<()>
+    where E: EntropyEncoder
+{
     for entry in entries.iter() {
         let f = try!(File::open(entry.filename.clone()));
         try!(encoder.analyze(f));
@@ -211,13 +307,10 @@ fn write_compressed_data(entries: &mut FileEntries,
     encoder.compress_finish()
 }
 
-fn write_offsets(entries: &FileEntries, encoder: &mut Encoder) -> Result<()> {
+fn write_offsets(entries: &FileEntries, writer: &mut BitWriter<File>) -> Result<()> {
     let entries_length = entries.len() as FileEntriesLength;
-    let skip_length = mem::size_of_val(&entries_length);
-
-    let mut file_clone = try!(encoder.get_output_mut().try_clone());
-    try!(file_clone.seek(SeekFrom::Start(skip_length as u64)));
-    let mut writer = BitWriter::new(file_clone);
+    let skip_length = codec::HEADER_LENGTH + mem::size_of_val(&entries_length);
+    try!(writer.seek(SeekFrom::Start(skip_length as u64)));
 
     for entry in entries {
         try!(writer.write_u64(entry.offset_bits));
@@ -225,15 +318,18 @@ fn write_offsets(entries: &FileEntries, encoder: &mut Encoder) -> Result<()> {
                           mem::size_of_val(&entry.filename_length_bytes) +
                           entry.filename_length_bytes as usize;
         let skip_length = skip_length as i64;
-        try!(writer.get_mut().seek(SeekFrom::Current(skip_length)));
+        try!(writer.seek(SeekFrom::Current(skip_length)));
     }
 
     Ok(())
 }
 
-fn load_header(input_filename: &str) -> Result<(FileEntries, BitReader<File>)> {
+fn load_header(input_filename: &str) -> Result<(Codec, FileEntries, BitReader<File>)> {
     let input = try!(File::open(input_filename));
     let mut reader = BitReader::new(input);
+
+    let codec = try!(Codec::read_header(&mut reader));
+
     let entries_length: FileEntriesLength = try!(reader.read_u64());
     let mut entries = Vec::with_capacity(entries_length as usize);
 
@@ -258,7 +354,7 @@ fn load_header(input_filename: &str) -> Result<(FileEntries, BitReader<File>)> {
         entries.push(entry);
     }
 
-    Ok((entries, reader))
+    Ok((codec, entries, reader))
 }
 
 fn create_parent_directories(filename: &str) -> Result<()> {
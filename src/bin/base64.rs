@@ -0,0 +1,108 @@
+extern crate getopts;
+
+extern crate rust_experiments;
+
+use getopts::Options;
+use rust_experiments::cli::base64::{decode, encode};
+use rust_experiments::encoding::base64::{Config, STANDARD, URL_SAFE};
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Result, stdin, stdout};
+use std::process;
+
+const DECODE_OPTION: &'static str = "d";
+const WRAP_OPTION: &'static str = "w";
+const URL_SAFE_OPTION: &'static str = "u";
+const IGNORE_GARBAGE_OPTION: &'static str = "i";
+const HELP_OPTION: &'static str = "h";
+const DEFAULT_WRAP: usize = 76;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {} [options] [FILE]", program);
+    print!("{}", opts.usage(&brief));
+}
+
+fn do_work(input: &mut Read, decoding: bool, config: Config, wrap: usize) -> Result<()> {
+    let mut input = BufReader::new(input);
+    let stdout = stdout();
+    let mut output = BufWriter::new(stdout.lock());
+
+    if decoding {
+        decode(&mut input, &mut output, config)
+    } else {
+        encode(&mut input, &mut output, config, wrap)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let program = &args[0];
+    let mut opts = Options::new();
+
+    opts.optflag(DECODE_OPTION, "decode", "decode data");
+    opts.optopt(WRAP_OPTION,
+                "wrap",
+                "wrap encoded lines after COLS characters (default 76), 0 disables wrapping",
+                "COLS");
+    opts.optflag(URL_SAFE_OPTION,
+                 "url-safe",
+                 "use the URL and filename safe alphabet");
+    opts.optflag(IGNORE_GARBAGE_OPTION,
+                 "ignore-garbage",
+                 "when decoding, ignore non-alphabet characters");
+    opts.optflag(HELP_OPTION, "help", "print this help menu");
+    let matches = match opts.parse(&args[1..]) {
+        Ok(matches) => matches,
+        Err(message) => {
+            println!("Error: {}", message);
+            print_usage(program, opts);
+            return;
+        }
+    };
+
+    if matches.opt_present(HELP_OPTION) {
+        print_usage(program, opts);
+        return;
+    }
+
+    if matches.free.len() > 1 {
+        println!("Error: extra operand {}", matches.free[1]);
+        print_usage(program, opts);
+        return;
+    }
+
+    let mut wrap = DEFAULT_WRAP;
+    if let Some(text) = matches.opt_str(WRAP_OPTION) {
+        match text.parse::<usize>() {
+            Ok(number) => wrap = number,
+            Err(message) => {
+                println!("Error: {}", message);
+                print_usage(program, opts);
+                return;
+            }
+        }
+    }
+
+    let config = Config {
+        strict: !matches.opt_present(IGNORE_GARBAGE_OPTION),
+        ..if matches.opt_present(URL_SAFE_OPTION) {
+            URL_SAFE
+        } else {
+            STANDARD
+        }
+    };
+    let decoding = matches.opt_present(DECODE_OPTION);
+
+    let result = if matches.free.is_empty() {
+        do_work(&mut stdin(), decoding, config, wrap)
+    } else {
+        let file_name = &matches.free[0];
+        File::open(file_name).and_then(|mut input| do_work(&mut input, decoding, config, wrap))
+    };
+
+    // the output is data, so errors go to stderr
+    if let Err(text) = result {
+        eprintln!("Error: {}", text);
+        process::exit(1);
+    }
+}
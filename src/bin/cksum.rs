@@ -0,0 +1,97 @@
+extern crate getopts;
+
+extern crate rust_experiments;
+
+use getopts::Options;
+use rust_experiments::encoding::checksum::{self, Checksum};
+use std::env;
+use std::fs::File;
+use std::io::{Read, Result, stdin};
+use std::process;
+
+const ALGORITHM_OPTION: &'static str = "a";
+const HELP_OPTION: &'static str = "h";
+
+const DEFAULT_ALGORITHM: &'static str = "crc32";
+
+fn usage(program: &str, opts: &Options) -> String {
+    let brief = format!("Usage: {} [options] [FILE]...", program);
+    format!("{}\nAlgorithms: {}\n",
+            opts.usage(&brief),
+            checksum::NAMES.join(", "))
+}
+
+// usage errors go to stderr and fail like read errors
+fn exit_with_usage(message: &str, program: &str, opts: &Options) -> ! {
+    eprintln!("Error: {}", message);
+    eprint!("{}", usage(program, opts));
+    process::exit(1);
+}
+
+fn do_work(input: &mut Read, name: &str, checksum: &mut Checksum) -> Result<()> {
+    checksum.reset();
+    let length = try!(checksum.update_from(input));
+    let digits = (checksum.width() as usize + 3) / 4;
+    println!("{:0width$x} {} {}",
+             checksum.finish(),
+             length,
+             name,
+             width = digits);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let program = &args[0];
+    let mut opts = Options::new();
+
+    opts.optopt(ALGORITHM_OPTION,
+                "algorithm",
+                &format!("the checksum to compute (default {})", DEFAULT_ALGORITHM),
+                "NAME");
+    opts.optflag(HELP_OPTION, "help", "print this help menu");
+    let matches = match opts.parse(&args[1..]) {
+        Ok(matches) => matches,
+        Err(message) => exit_with_usage(&message.to_string(), program, &opts),
+    };
+
+    if matches.opt_present(HELP_OPTION) {
+        print!("{}", usage(program, &opts));
+        return;
+    }
+
+    let algorithm = matches.opt_str(ALGORITHM_OPTION)
+        .unwrap_or_else(|| DEFAULT_ALGORITHM.to_string());
+    let mut checksum = match checksum::by_name(&algorithm) {
+        Some(checksum) => checksum,
+        None => {
+            let message = format!("unknown algorithm {}", algorithm);
+            exit_with_usage(&message, program, &opts)
+        }
+    };
+
+    let file_names = if matches.free.is_empty() {
+        vec!["-".to_string()]
+    } else {
+        matches.free.clone()
+    };
+
+    // the remaining files are still checked after an error, which goes to stderr
+    let mut failed = false;
+    for file_name in &file_names {
+        let result = if file_name == "-" {
+            do_work(&mut stdin(), file_name, &mut *checksum)
+        } else {
+            File::open(file_name)
+                .and_then(|mut input| do_work(&mut input, file_name, &mut *checksum))
+        };
+        if let Err(text) = result {
+            eprintln!("Error: {}: {}", file_name, text);
+            failed = true;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,111 @@
+extern crate getopts;
+
+extern crate rust_experiments;
+
+use getopts::Options;
+use rust_experiments::encoding::unicode::{CHARSETS, Charset, Transcoder};
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Read, Result, Write, stdin, stdout};
+use std::process;
+
+const FROM_OPTION: &'static str = "f";
+const TO_OPTION: &'static str = "t";
+const LOSSY_OPTION: &'static str = "c";
+const BOM_OPTION: &'static str = "b";
+const LIST_OPTION: &'static str = "l";
+const HELP_OPTION: &'static str = "h";
+const DEFAULT_CHARSET: Charset = Charset::Utf8;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {} [options] [FILE]", program);
+    print!("{}", opts.usage(&brief));
+}
+
+fn do_work(input: &mut Read, transcoder: &mut Transcoder) -> Result<()> {
+    let stdout = stdout();
+    let mut output = BufWriter::new(stdout.lock());
+    try!(transcoder.copy(input, &mut output));
+    output.flush()
+}
+
+fn parse_charset(matches: &getopts::Matches, option: &str) -> Option<Charset> {
+    match matches.opt_str(option) {
+        Some(name) => Charset::from_name(&name),
+        None => Some(DEFAULT_CHARSET),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let program = &args[0];
+    let mut opts = Options::new();
+
+    opts.optopt(FROM_OPTION,
+                "from-code",
+                "the encoding of the input (default UTF-8)",
+                "NAME");
+    opts.optopt(TO_OPTION,
+                "to-code",
+                "the encoding of the output (default UTF-8)",
+                "NAME");
+    opts.optflag(LOSSY_OPTION,
+                 "lossy",
+                 "replace invalid and unencodable characters instead of failing");
+    opts.optflag(BOM_OPTION, "bom", "start Unicode output with a byte order mark");
+    opts.optflag(LIST_OPTION, "list", "list the supported encodings");
+    opts.optflag(HELP_OPTION, "help", "print this help menu");
+    let matches = match opts.parse(&args[1..]) {
+        Ok(matches) => matches,
+        Err(message) => {
+            println!("Error: {}", message);
+            print_usage(program, opts);
+            return;
+        }
+    };
+
+    if matches.opt_present(HELP_OPTION) {
+        print_usage(program, opts);
+        return;
+    }
+
+    if matches.free.len() > 1 {
+        println!("Error: extra operand {}", matches.free[1]);
+        print_usage(program, opts);
+        return;
+    }
+
+    if matches.opt_present(LIST_OPTION) {
+        for charset in &CHARSETS {
+            println!("{}", charset.name());
+        }
+        return;
+    }
+
+    let (from, to) = match (parse_charset(&matches, FROM_OPTION),
+                            parse_charset(&matches, TO_OPTION)) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            println!("Error: unsupported encoding, see --list");
+            print_usage(program, opts);
+            return;
+        }
+    };
+
+    let mut transcoder = Transcoder::new(from, to);
+    transcoder.set_lossy(matches.opt_present(LOSSY_OPTION));
+    transcoder.set_emit_bom(matches.opt_present(BOM_OPTION));
+
+    let result = if matches.free.is_empty() {
+        do_work(&mut stdin(), &mut transcoder)
+    } else {
+        let file_name = &matches.free[0];
+        File::open(file_name).and_then(|mut input| do_work(&mut input, &mut transcoder))
+    };
+
+    // the output is data, so errors go to stderr
+    if let Err(text) = result {
+        eprintln!("Error: {}", text);
+        process::exit(1);
+    }
+}
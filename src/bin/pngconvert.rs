@@ -7,11 +7,14 @@ extern crate nalgebra;
 
 extern crate rust_experiments;
 
-use clap::App;
+use clap::{App, Arg};
 use image::DynamicImage;
 use nalgebra::{Dot, Vector3 as Vec3, Vector4 as Vec4};
+use rust_experiments::encoding::arithmetic::{AdaptiveModel, ArithmeticDecoder,
+                                             ArithmeticEncoder, StaticModel};
 use rust_experiments::encoding::bitreader::BitReader;
 use rust_experiments::encoding::bitwriter::BitWriter;
+use rust_experiments::encoding::codec::{Codec, EntropyDecoder, EntropyEncoder};
 use rust_experiments::encoding::huffman::{HuffmanEncoder, HuffmanDecoder};
 use std::fs::File;
 use std::io::{Cursor, Error, ErrorKind, Result};
@@ -216,7 +219,7 @@ impl RgbImage {
         Ok(output)
     }
 
-    pub fn compress(&self, output_filename: &str) -> Result<()> {
+    pub fn compress(&self, output_filename: &str, codec: Codec) -> Result<()> {
         let n = self.width * self.height;
 
         let mut y_vec = Vec::with_capacity(n);
@@ -253,25 +256,50 @@ impl RgbImage {
         let height = self.height as u32;
         let f = try!(File::create(output_filename));
         let mut header_writer = BitWriter::new(f);
+        try!(codec.write_header(&mut header_writer));
         try!(header_writer.write_u32(width));
         try!(header_writer.write_u32(height));
 
-        let mut encoder = HuffmanEncoder::new(header_writer.get_mut(), CHAR_LENGTH);
         let data = writer.get_ref().as_slice();
         assert_eq!(pixels_length, data.len());
 
+        let output = header_writer.get_mut();
+        match codec {
+            Codec::Huffman => {
+                Self::compress_pixels(HuffmanEncoder::new(output, CHAR_LENGTH), data)
+            }
+            Codec::Arithmetic => {
+                let encoder: ArithmeticEncoder<_, StaticModel> = ArithmeticEncoder::new(output);
+                Self::compress_pixels(encoder, data)
+            }
+            Codec::AdaptiveArithmetic => {
+                let encoder: ArithmeticEncoder<_, AdaptiveModel> = ArithmeticEncoder::new(output);
+                Self::compress_pixels(encoder, data)
+            }
+        }
+    }
+
+    fn compress_pixels<E: EntropyEncoder>(mut encoder: E, data: &[u8]) -> Result<()> {
         let reader = Cursor::new(data);
         try!(encoder.analyze(reader.clone()));
         try!(encoder.analyze_finish());
         try!(encoder.compress(reader));
-        try!(encoder.compress_finish());
+        encoder.compress_finish()
+    }
 
+    fn decode_pixels<D: EntropyDecoder>(mut decoder: D,
+                                        output: &mut Vec<u8>,
+                                        pixels_length_bits: u64)
+                                        -> Result<()> {
+        let data_offset_bit = decoder.data_offset_bit();
+        try!(decoder.decode(output, data_offset_bit, pixels_length_bits));
         Ok(())
     }
 
     pub fn decompress(input_filename: &str, output_filename: &str) -> Result<()> {
         let f = try!(File::open(input_filename));
         let mut reader = BitReader::new(f);
+        let codec = try!(Codec::read_header(&mut reader));
         let width = try!(reader.read_u32()) as usize;
         let height = try!(reader.read_u32()) as usize;
         let n = width * height;
@@ -279,11 +307,24 @@ impl RgbImage {
         let pixels_length_bits = pixels_length * 8;
         let mut pixels_writer = Vec::with_capacity(pixels_length);
 
-        let mut coder = try!(HuffmanDecoder::new(reader.get_ref()));
-        let data_offset_bit = coder.data_offset_bit();
-        try!(coder.decode(&mut pixels_writer,
-                          data_offset_bit,
-                          pixels_length_bits as u64));
+        let input = try!(reader.into_inner());
+        let pixels_length_bits = pixels_length_bits as u64;
+        try!(match codec {
+            Codec::Huffman => {
+                let decoder = try!(HuffmanDecoder::new(input));
+                Self::decode_pixels(decoder, &mut pixels_writer, pixels_length_bits)
+            }
+            Codec::Arithmetic => {
+                let decoder: ArithmeticDecoder<_, StaticModel> =
+                    try!(ArithmeticDecoder::new(input));
+                Self::decode_pixels(decoder, &mut pixels_writer, pixels_length_bits)
+            }
+            Codec::AdaptiveArithmetic => {
+                let decoder: ArithmeticDecoder<_, AdaptiveModel> =
+                    try!(ArithmeticDecoder::new(input));
+                Self::decode_pixels(decoder, &mut pixels_writer, pixels_length_bits)
+            }
+        });
         let mut reader = BitReader::new(pixels_writer.as_slice());
 
         assert_eq!(pixels_length, pixels_writer.len());
@@ -370,6 +411,12 @@ fn main() {
                 -a <nearest|bilinear|grayscale|compress|decompress> 'Conversion algorithm'";
     let matches = App::new("PNG Convert")
         .args_from_usage(usage)
+        .arg(Arg::with_name("e")
+            .short("e")
+            .takes_value(true)
+            .value_name("codec")
+            .possible_values(&["huffman", "arithmetic", "adaptive"])
+            .help("Entropy coder for compress"))
         .get_matches();
 
     let algorithm = value_t_or_exit!(matches, "a", String);
@@ -380,8 +427,20 @@ fn main() {
     } else {
         DEFAULT_FACTOR
     };
+    let codec_name = matches.value_of("e").unwrap_or("huffman");
+    let codec = match Codec::from_name(codec_name) {
+        Some(codec) => codec,
+        None => {
+            println!("Error: unknown entropy coder '{}'", codec_name);
+            return;
+        }
+    };
 
-    match do_checked_main(input_filename, output_filename, factor, algorithm.as_str()) {
+    match do_checked_main(input_filename,
+                          output_filename,
+                          factor,
+                          algorithm.as_str(),
+                          codec) {
         Ok(_) => println!("OK"),
         Err(e) => println!("Error: {:?}", e),
     }
@@ -390,7 +449,8 @@ fn main() {
 fn do_checked_main(input_filename: String,
                    output_filename: String,
                    factor: u32,
-                   algorithm: &str)
+                   algorithm: &str,
+                   codec: Codec)
                    -> Result<()> {
     // FIXME: refactor
     if algorithm == "decompress" {
@@ -412,7 +472,7 @@ fn do_checked_main(input_filename: String,
                 let factor = factor as usize;
                 let input_image = RgbImage::with_buffer(image_data.into_vec(), width, height);
                 match algorithm {
-                    "compress" => input_image.compress(output_filename.as_str()),
+                    "compress" => input_image.compress(output_filename.as_str(), codec),
                     _ => {
                         let output_image = match algorithm {
                             "nearest" => try!(input_image.scale_nearest_neighbor(factor)),
@@ -0,0 +1,128 @@
+use encoding::base64::{Base64Reader, Base64Writer, Config, MIME};
+use std::io::{Read, Result, Write, copy};
+
+// GNU base64 style: "\n"-terminated lines of `wrap` characters, 0 disables wrapping
+pub fn encode(input: &mut Read, output: &mut Write, config: Config, wrap: usize) -> Result<()> {
+    let config = Config { line_length: None, ..config };
+    let mut lines = LineWriter {
+        output: output,
+        wrap: wrap,
+        column: 0,
+    };
+
+    {
+        let mut encoder = Base64Writer::new(&mut lines, config);
+        let _ = try!(copy(input, &mut encoder));
+        try!(encoder.finish());
+    }
+
+    if lines.wrap > 0 && lines.column > 0 {
+        try!(lines.output.write_all(b"\n"));
+    }
+    lines.output.flush()
+}
+
+pub fn decode(input: &mut Read, output: &mut Write, config: Config) -> Result<()> {
+    // wrapped input is accepted even in strict mode
+    let config = Config { line_length: MIME.line_length, ..config };
+    let mut decoder = Base64Reader::new(input, config);
+    let _ = try!(copy(&mut decoder, output));
+    output.flush()
+}
+
+struct LineWriter<'a> {
+    output: &'a mut Write,
+    wrap: usize,
+    column: usize,
+}
+
+impl<'a> Write for LineWriter<'a> {
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        if self.wrap == 0 {
+            try!(self.output.write_all(data));
+            self.column += data.len();
+            return Ok(data.len());
+        }
+
+        let written = data.len();
+        let mut data = data;
+        while !data.is_empty() {
+            let length = (self.wrap - self.column).min(data.len());
+            try!(self.output.write_all(&data[..length]));
+            self.column += length;
+            data = &data[length..];
+
+            if self.column == self.wrap {
+                try!(self.output.write_all(b"\n"));
+                self.column = 0;
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.output.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::base64::{STANDARD, URL_SAFE};
+    use super::*;
+
+    quickcheck! {
+        fn random_roundtrip(data: Vec<u8>, wrap: usize) -> bool {
+            let wrap = wrap % 10;
+            let mut encoded = vec![];
+            encode(&mut &data[..], &mut encoded, STANDARD, wrap).unwrap();
+
+            let mut decoded = vec![];
+            let strict = Config { strict: true, ..STANDARD };
+            decode(&mut &encoded[..], &mut decoded, strict).unwrap();
+            decoded == data
+        }
+    }
+
+    #[test]
+    fn wrap() {
+        assert_eq!("Zm9vYmFy\n", encode_str(b"foobar", STANDARD, 76));
+        assert_eq!("Zm9v\nYmFy\n", encode_str(b"foobar", STANDARD, 4));
+        assert_eq!("Zm9vYg==", encode_str(b"foob", STANDARD, 0));
+        assert_eq!("Zm9\nvYg\n==\n", encode_str(b"foob", STANDARD, 3));
+        assert_eq!("", encode_str(b"", STANDARD, 76));
+        assert_eq!("-_8=\n", encode_str(&[0xfb, 0xff], URL_SAFE, 76));
+    }
+
+    #[test]
+    fn ignore_garbage() {
+        let strict = Config { strict: true, ..STANDARD };
+        let mut output = vec![];
+        assert!(decode(&mut &b"Zm9v\r\nYmFy\n"[..], &mut output, strict).is_ok());
+        assert_eq!(b"foobar".to_vec(), output);
+
+        let mut output = vec![];
+        assert!(decode(&mut &b"Zm9v*YmFy\n"[..], &mut output, strict).is_err());
+
+        let mut output = vec![];
+        assert!(decode(&mut &b"Zm9v*YmFy\n"[..], &mut output, STANDARD).is_ok());
+        assert_eq!(b"foobar".to_vec(), output);
+    }
+
+    #[test]
+    fn concatenated() {
+        let strict = Config { strict: true, ..STANDARD };
+        let mut output = vec![];
+        assert!(decode(&mut &b"Zg==\nZg=="[..], &mut output, strict).is_ok());
+        assert_eq!(b"ff".to_vec(), output);
+
+        let mut output = vec![];
+        assert!(decode(&mut &b"Zg==\nZg==\n"[..], &mut output, STANDARD).is_ok());
+        assert_eq!(b"ff".to_vec(), output);
+    }
+
+    fn encode_str(data: &[u8], config: Config, wrap: usize) -> String {
+        let mut output = vec![];
+        encode(&mut &data[..], &mut output, config, wrap).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+}
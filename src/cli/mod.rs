@@ -1,3 +1,4 @@
+pub mod base64;
 pub mod head;
 pub mod tail;
 pub mod wc;
@@ -1,5 +1,11 @@
+use encoding::unicode::segmentation::{grapheme_width, graphemes};
 use std::cmp::max;
-use std::io::Read;
+use std::io::{ErrorKind, Read};
+use std::str;
+
+const BUFFER_SIZE: usize = 8192;
+// longer grapheme clusters are counted as several characters, this keeps memory and time linear
+const MAX_GRAPHEME_LENGTH: usize = 1024;
 
 #[derive(PartialEq, Debug)]
 pub struct Counters {
@@ -10,57 +16,129 @@ pub struct Counters {
     pub max_line_length: usize,
 }
 
-#[allow(while_let_on_iterator)]
+// characters are extended grapheme clusters, and line lengths are in terminal columns
 pub fn count(input: &mut Read) -> Counters {
-    let mut bytes = 0;
-    let mut characters = 0;
-    let mut newlines = 0;
-    let mut words = 0;
-    let mut max_line_length = 0;
-    let mut line_length = 0;
-
-    let is_whitespace = |ch| ch == ' ' || ch == '\n' || ch == '\t';
-
-    let mut word_started = false;
-    let mut it = input.chars();
-
-    while let Some(Ok(ch)) = it.next() {
-        characters += 1;
-        bytes += ch.len_utf8();
-        let newline = ch == '\n';
-
-        if is_whitespace(ch) {
-            if word_started {
-                word_started = false;
+    let mut counting = Counting {
+        counters: Counters {
+            bytes: 0,
+            characters: 0,
+            newlines: 0,
+            words: 0,
+            max_line_length: 0,
+        },
+        word_started: false,
+        line_length: 0,
+    };
+
+    let mut buffer = vec![0; BUFFER_SIZE];
+    // an incomplete UTF-8 sequence at the end of the last chunk
+    let mut pending = vec![];
+    // the last grapheme, which may continue in the next chunk
+    let mut text = String::new();
+
+    loop {
+        let length = match input.read(&mut buffer[..]) {
+            Ok(0) => break,
+            Ok(length) => length,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        counting.counters.bytes += length;
+        pending.extend_from_slice(&buffer[..length]);
+        decode_utf8(&mut pending, &mut text);
+
+        let last_start = match graphemes(&text).last() {
+            Some(last) => text.len() - last.len(),
+            None => continue,
+        };
+        for grapheme in graphemes(&text[..last_start]) {
+            counting.push(grapheme);
+        }
+        let _ = text.drain(..last_start);
+        if text.len() > MAX_GRAPHEME_LENGTH {
+            counting.push(&text);
+            text.clear();
+        }
+    }
+
+    text.push_str(&String::from_utf8_lossy(&pending[..]));
+    for grapheme in graphemes(&text) {
+        counting.push(grapheme);
+    }
+    counting.finish()
+}
+
+struct Counting {
+    counters: Counters,
+    word_started: bool,
+    line_length: usize,
+}
+
+impl Counting {
+    fn push(&mut self, grapheme: &str) {
+        let newline = grapheme.ends_with('\n');
+        let is_whitespace = grapheme == " " || grapheme == "\t" || newline;
+        self.counters.characters += 1;
+
+        if is_whitespace {
+            if self.word_started {
+                self.word_started = false;
             }
             if newline {
-                newlines += 1;
+                self.counters.newlines += 1;
             }
-        } else if !word_started {
-            word_started = true;
-            words += 1;
+        } else if !self.word_started {
+            self.word_started = true;
+            self.counters.words += 1;
         }
 
         if newline {
-            max_line_length = max(line_length, max_line_length);
-            line_length = 0;
+            self.counters.max_line_length = max(self.line_length, self.counters.max_line_length);
+            self.line_length = 0;
+        } else if grapheme == "\t" {
+            self.line_length += 1;
         } else {
-            line_length += 1;
+            self.line_length += grapheme_width(grapheme);
         }
     }
 
-    Counters {
-        bytes: bytes,
-        characters: characters,
-        newlines: newlines,
-        words: words,
-        max_line_length: max_line_length,
+    fn finish(mut self) -> Counters {
+        self.counters.max_line_length = max(self.line_length, self.counters.max_line_length);
+        self.counters
     }
 }
 
+// moves the decoded part of `input` to `output`, invalid sequences become U+FFFD
+fn decode_utf8(input: &mut Vec<u8>, output: &mut String) {
+    let mut start = 0;
+    while start < input.len() {
+        match str::from_utf8(&input[start..]) {
+            Ok(text) => {
+                output.push_str(text);
+                start = input.len();
+            }
+            Err(e) => {
+                let valid = start + e.valid_up_to();
+                output.push_str(str::from_utf8(&input[start..valid]).unwrap());
+                match e.error_len() {
+                    Some(length) => {
+                        output.push('\u{fffd}');
+                        start = valid + length;
+                    }
+                    None => {
+                        start = valid;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let _ = input.drain(..start);
+}
+
 #[cfg(test)]
 mod tests {
-    use std::io::BufReader;
+    use std::io::{BufReader, Read, Result};
     use super::*;
 
     #[test]
@@ -75,6 +153,65 @@ mod tests {
         count_assert(expect, " привет, \n\nраст\t123 ".as_bytes());
     }
 
+    #[test]
+    fn graphemes() {
+        let expect = Counters {
+            bytes: 27,
+            characters: 11,
+            newlines: 2,
+            words: 3,
+            max_line_length: 9,
+        };
+        count_assert(expect, "cafe\u{301}\r\n日本語 \u{1f44d}\u{1f3fd}\n".as_bytes());
+    }
+
+    quickcheck! {
+        fn random_chunks(text: String) -> bool {
+            let input = text.as_bytes();
+            let mut trickle = Trickle(input);
+            count(&mut trickle) == count(&mut &input[..])
+        }
+    }
+
+    #[test]
+    fn long_input() {
+        let text = "e\u{301}".repeat(5000);
+        let mut input = text.as_bytes();
+        let result = count(&mut input);
+        assert_eq!(15000, result.bytes);
+        assert_eq!(5000, result.characters);
+        assert_eq!(5000, result.max_line_length);
+
+        let input = b"\xff\xe2\x82 x";
+        assert_eq!(4, count(&mut Trickle(&input[..])).characters);
+    }
+
+    #[test]
+    fn long_grapheme() {
+        let mut text = String::from("e");
+        text.push_str(&"\u{301}".repeat(2 << 20));
+        let mut input = text.as_bytes();
+        let result = count(&mut input);
+        assert_eq!(text.len(), result.bytes);
+        assert!(result.characters <= text.len() / MAX_GRAPHEME_LENGTH + 1);
+        assert_eq!(1, result.words);
+        assert_eq!(1, result.max_line_length);
+    }
+
+    // returns one byte per read
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+            if self.0.is_empty() || buffer.is_empty() {
+                return Ok(0);
+            }
+            buffer[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
     fn count_assert(expect: Counters, input: &[u8]) {
         let mut reader = BufReader::new(input);
         let result = count(&mut reader);
@@ -0,0 +1,603 @@
+use encoding::bitreader::BitReader;
+use encoding::bitwriter::BitWriter;
+use std::io::{BufReader, Error, ErrorKind, Read, Result, Seek, Write};
+
+const SYMBOLS: usize = 256;
+
+const TOP: u64 = 0xffff_ffff;
+const HALF: u64 = 1 << 31;
+const QUARTER: u64 = 1 << 30;
+const THREE_QUARTERS: u64 = HALF + QUARTER;
+
+const MAX_TOTAL: u32 = 1 << 16;
+const ADAPTIVE_INCREMENT: u32 = 32;
+
+pub trait Model: Sized {
+    fn new() -> Self;
+    fn analyze(&mut self, symbol: u8);
+    fn analyze_finish(&mut self);
+    fn write_header<W: Write>(&self, output: &mut BitWriter<W>) -> Result<()>;
+    fn read_header<R: Read>(input: &mut BitReader<R>) -> Result<Self>;
+    fn reset(&mut self);
+    fn total(&self) -> u32;
+    fn symbol_range(&self, symbol: u8) -> (u32, u32);
+    fn find_symbol(&self, count: u32) -> (u8, u32, u32);
+    fn update(&mut self, symbol: u8);
+}
+
+pub struct StaticModel {
+    counts: Vec<u64>,
+    frequencies: Vec<u32>,
+    cumulative: Vec<u32>,
+}
+
+pub struct AdaptiveModel {
+    frequencies: Vec<u32>,
+    total: u32,
+}
+
+impl StaticModel {
+    fn compute_cumulative(&mut self) {
+        self.cumulative = Vec::with_capacity(SYMBOLS + 1);
+        let mut sum = 0;
+        self.cumulative.push(sum);
+        for &frequency in &self.frequencies {
+            sum += frequency;
+            self.cumulative.push(sum);
+        }
+    }
+}
+
+impl Model for StaticModel {
+    fn new() -> Self {
+        StaticModel {
+            counts: vec![0; SYMBOLS],
+            frequencies: vec![0; SYMBOLS],
+            cumulative: vec![0; SYMBOLS + 1],
+        }
+    }
+
+    fn analyze(&mut self, symbol: u8) {
+        self.counts[symbol as usize] += 1;
+    }
+
+    fn analyze_finish(&mut self) {
+        let counts_total = self.counts.iter().sum::<u64>();
+        let target_total = (MAX_TOTAL as usize - SYMBOLS) as u64;
+
+        for (frequency, &count) in self.frequencies.iter_mut().zip(self.counts.iter()) {
+            *frequency = if count == 0 {
+                0
+            } else if counts_total <= target_total {
+                count as u32
+            } else {
+                let scaled = count * target_total / counts_total;
+                scaled as u32 + 1
+            };
+        }
+
+        // the header of an empty model would be rejected by read_header
+        if counts_total == 0 {
+            self.frequencies[0] = 1;
+        }
+
+        self.compute_cumulative();
+    }
+
+    fn write_header<W: Write>(&self, output: &mut BitWriter<W>) -> Result<()> {
+        let used_symbols = self.frequencies.iter().filter(|&&i| i > 0).count();
+        try!(output.write_u16(used_symbols as u16));
+
+        for (symbol, &frequency) in self.frequencies.iter().enumerate() {
+            if frequency > 0 {
+                try!(output.write_u8(symbol as u8));
+                try!(output.write_u16(frequency as u16));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_header<R: Read>(input: &mut BitReader<R>) -> Result<Self> {
+        let mut result = Self::new();
+        let used_symbols = try!(input.read_u16()) as usize;
+        if used_symbols > SYMBOLS {
+            let e = Error::new(ErrorKind::InvalidData, "Too many symbols");
+            return Err(e);
+        }
+
+        for _ in 0..used_symbols {
+            let symbol = try!(input.read_u8());
+            let frequency = try!(input.read_u16());
+            result.frequencies[symbol as usize] = frequency as u32;
+        }
+
+        result.compute_cumulative();
+        if result.total() == 0 {
+            let e = Error::new(ErrorKind::InvalidData, "No symbols");
+            return Err(e);
+        } else if result.total() > MAX_TOTAL {
+            let e = Error::new(ErrorKind::InvalidData, "Frequencies are too high");
+            return Err(e);
+        }
+
+        Ok(result)
+    }
+
+    fn reset(&mut self) {}
+
+    fn total(&self) -> u32 {
+        self.cumulative[SYMBOLS]
+    }
+
+    fn symbol_range(&self, symbol: u8) -> (u32, u32) {
+        let symbol = symbol as usize;
+        (self.cumulative[symbol], self.cumulative[symbol + 1])
+    }
+
+    fn find_symbol(&self, count: u32) -> (u8, u32, u32) {
+        let mut left = 0;
+        let mut right = SYMBOLS;
+        while right - left > 1 {
+            let middle = left + (right - left) / 2;
+            if self.cumulative[middle] <= count {
+                left = middle;
+            } else {
+                right = middle;
+            }
+        }
+
+        (left as u8, self.cumulative[left], self.cumulative[left + 1])
+    }
+
+    fn update(&mut self, _: u8) {}
+}
+
+impl Model for AdaptiveModel {
+    fn new() -> Self {
+        AdaptiveModel {
+            frequencies: vec![1; SYMBOLS],
+            total: SYMBOLS as u32,
+        }
+    }
+
+    fn analyze(&mut self, _: u8) {}
+
+    fn analyze_finish(&mut self) {}
+
+    fn write_header<W: Write>(&self, _: &mut BitWriter<W>) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_header<R: Read>(_: &mut BitReader<R>) -> Result<Self> {
+        Ok(Self::new())
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn total(&self) -> u32 {
+        self.total
+    }
+
+    fn symbol_range(&self, symbol: u8) -> (u32, u32) {
+        let symbol = symbol as usize;
+        let low = self.frequencies[0..symbol].iter().sum::<u32>();
+        (low, low + self.frequencies[symbol])
+    }
+
+    fn find_symbol(&self, count: u32) -> (u8, u32, u32) {
+        let mut low = 0;
+        for (symbol, &frequency) in self.frequencies.iter().enumerate() {
+            let high = low + frequency;
+            if count < high {
+                return (symbol as u8, low, high);
+            }
+            low = high;
+        }
+
+        unreachable!()
+    }
+
+    fn update(&mut self, symbol: u8) {
+        self.frequencies[symbol as usize] += ADAPTIVE_INCREMENT;
+        self.total += ADAPTIVE_INCREMENT;
+
+        if self.total > MAX_TOTAL {
+            self.total = 0;
+            for frequency in &mut self.frequencies {
+                *frequency = (*frequency + 1) / 2;
+                self.total += *frequency;
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum State {
+    Initial,
+    Analyzed,
+    Compressed,
+}
+
+pub struct ArithmeticEncoder<W: Write, M: Model> {
+    state: State,
+    output: BitWriter<W>,
+    model: M,
+    low: u64,
+    high: u64,
+    pending_bits: u64,
+}
+
+impl<W: Write, M: Model> ArithmeticEncoder<W, M> {
+    pub fn new(output: W) -> Self {
+        ArithmeticEncoder {
+            state: State::Initial,
+            output: BitWriter::new(output),
+            model: M::new(),
+            low: 0,
+            high: TOP,
+            pending_bits: 0,
+        }
+    }
+
+    pub fn analyze<R>(&mut self, input: R) -> Result<u64>
+        where R: Read
+    {
+        assert_eq!(State::Initial, self.state);
+
+        let mut bytes_read = 0;
+        for byte in BufReader::new(input).bytes() {
+            self.model.analyze(try!(byte));
+            bytes_read += 1;
+        }
+
+        Ok(bytes_read * 8)
+    }
+
+    pub fn analyze_finish(&mut self) -> Result<()> {
+        assert_eq!(State::Initial, self.state);
+        self.state = State::Analyzed;
+
+        self.model.analyze_finish();
+        self.model.write_header(&mut self.output)
+    }
+
+    pub fn compress<R>(&mut self, input: R) -> Result<u64>
+        where R: Read
+    {
+        assert_eq!(State::Analyzed, self.state);
+
+        let start_position = self.output.position();
+        self.model.reset();
+        self.low = 0;
+        self.high = TOP;
+        self.pending_bits = 0;
+
+        for byte in BufReader::new(input).bytes() {
+            try!(self.encode_symbol(try!(byte)));
+        }
+        try!(self.finish_segment());
+
+        Ok(self.output.position() - start_position)
+    }
+
+    pub fn compress_finish(&mut self) -> Result<()> {
+        assert_eq!(State::Analyzed, self.state);
+        self.state = State::Compressed;
+
+        self.output.flush()
+    }
+
+    pub fn position(&self) -> u64 {
+        self.output.position()
+    }
+
+    pub fn get_output_ref(&self) -> &W {
+        self.output.get_ref()
+    }
+
+    pub fn get_output_mut(&mut self) -> &mut W {
+        self.output.get_mut()
+    }
+
+    fn encode_symbol(&mut self, symbol: u8) -> Result<()> {
+        let (symbol_low, symbol_high) = self.model.symbol_range(symbol);
+        if symbol_low == symbol_high {
+            let e = Error::new(ErrorKind::InvalidInput, "Symbol wasn't analyzed");
+            return Err(e);
+        }
+
+        let total = self.model.total() as u64;
+        let range = self.high - self.low + 1;
+        self.high = self.low + range * (symbol_high as u64) / total - 1;
+        self.low += range * (symbol_low as u64) / total;
+
+        loop {
+            if self.high < HALF {
+                try!(self.write_bit_with_pending(false));
+            } else if self.low >= HALF {
+                try!(self.write_bit_with_pending(true));
+                self.low -= HALF;
+                self.high -= HALF;
+            } else if self.low >= QUARTER && self.high < THREE_QUARTERS {
+                self.pending_bits += 1;
+                self.low -= QUARTER;
+                self.high -= QUARTER;
+            } else {
+                break;
+            }
+
+            self.low <<= 1;
+            self.high = (self.high << 1) | 1;
+        }
+
+        self.model.update(symbol);
+        Ok(())
+    }
+
+    fn finish_segment(&mut self) -> Result<()> {
+        self.pending_bits += 1;
+        let data = self.low >= QUARTER;
+        self.write_bit_with_pending(data)
+    }
+
+    fn write_bit_with_pending(&mut self, data: bool) -> Result<()> {
+        try!(self.output.write_bit(data));
+        while self.pending_bits > 0 {
+            try!(self.output.write_bit(!data));
+            self.pending_bits -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write, M: Model> Drop for ArithmeticEncoder<W, M> {
+    fn drop(&mut self) {
+        if self.state == State::Analyzed {
+            let _ = self.compress_finish();
+        }
+    }
+}
+
+pub struct ArithmeticDecoder<R: Read + Seek, M: Model> {
+    input: BitReader<R>,
+    model: M,
+    data_offset_bit: u64,
+}
+
+impl<R: Read + Seek, M: Model> ArithmeticDecoder<R, M> {
+    pub fn new(input: R) -> Result<Self> {
+        let mut input = BitReader::new(input);
+        let model = match M::read_header(&mut input) {
+            Ok(model) => model,
+            Err(_) => {
+                let e = Error::new(ErrorKind::InvalidInput, "Failed to read the header");
+                return Err(e);
+            }
+        };
+        let data_offset_bit = input.position();
+
+        let result = ArithmeticDecoder {
+            input: input,
+            model: model,
+            data_offset_bit: data_offset_bit,
+        };
+        Ok(result)
+    }
+
+    pub fn decode(&mut self,
+                  output: &mut Write,
+                  offset_bit: u64,
+                  original_length_bits: u64)
+                  -> Result<u64> {
+        let original_length_bytes = original_length_bits / 8;
+        if original_length_bytes == 0 {
+            return Ok(0);
+        }
+
+        try!(self.input.set_position(offset_bit));
+        self.model.reset();
+
+        let mut low = 0;
+        let mut high = TOP;
+        let mut value = 0;
+        for _ in 0..32 {
+            value = (value << 1) | self.read_bit();
+        }
+
+        for _ in 0..original_length_bytes {
+            let total = self.model.total() as u64;
+            let range = high - low + 1;
+            let count = ((value - low + 1) * total - 1) / range;
+            if count >= total {
+                return Err(corrupted_data());
+            }
+            let (symbol, symbol_low, symbol_high) = self.model.find_symbol(count as u32);
+            if symbol_low == symbol_high {
+                return Err(corrupted_data());
+            }
+
+            high = low + range * (symbol_high as u64) / total - 1;
+            low += range * (symbol_low as u64) / total;
+
+            loop {
+                if high >= HALF {
+                    if low >= HALF {
+                        value -= HALF;
+                        low -= HALF;
+                        high -= HALF;
+                    } else if low >= QUARTER && high < THREE_QUARTERS {
+                        value -= QUARTER;
+                        low -= QUARTER;
+                        high -= QUARTER;
+                    } else {
+                        break;
+                    }
+                }
+
+                low <<= 1;
+                high = (high << 1) | 1;
+                value = (value << 1) | self.read_bit();
+            }
+
+            try!(output.write_all(&[symbol]));
+            self.model.update(symbol);
+        }
+
+        try!(output.flush());
+        Ok(original_length_bytes * 8)
+    }
+
+    pub fn data_offset_bit(&self) -> u64 {
+        self.data_offset_bit
+    }
+
+    pub fn get_input_mut(&mut self) -> &mut R {
+        self.input.get_mut()
+    }
+
+    // bits after the end of the stream don't affect the result
+    fn read_bit(&mut self) -> u64 {
+        match self.input.read_bit() {
+            Ok(true) => 1,
+            _ => 0,
+        }
+    }
+}
+
+fn corrupted_data() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted data")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "mississippi river";
+
+    quickcheck! {
+        fn random_static(input: Vec<u8>) -> bool {
+            check_data::<StaticModel>(&input[..])
+        }
+
+        fn random_adaptive(input: Vec<u8>) -> bool {
+            check_data::<AdaptiveModel>(&input[..])
+        }
+
+        fn random_multiple_static(inputs: Vec<Vec<u8>>) -> bool {
+            check_multiple::<StaticModel>(inputs)
+        }
+
+        fn random_multiple_adaptive(inputs: Vec<Vec<u8>>) -> bool {
+            check_multiple::<AdaptiveModel>(inputs)
+        }
+    }
+
+    #[test]
+    fn single_input() {
+        for input in &[INPUT_TEXT, "12", "3", ""] {
+            assert!(check_data::<StaticModel>(input.as_bytes()));
+            assert!(check_data::<AdaptiveModel>(input.as_bytes()));
+        }
+
+        let long_input = (0..100_000).map(|i| (i % 7 + i % 3) as u8).collect::<Vec<u8>>();
+        assert!(check_data::<StaticModel>(&long_input[..]));
+        assert!(check_data::<AdaptiveModel>(&long_input[..]));
+    }
+
+    #[test]
+    fn skewed_distribution() {
+        let mut input = vec![b'a'; 10_000];
+        input.extend_from_slice(b"bc");
+
+        let mut coder: ArithmeticEncoder<_, StaticModel> = ArithmeticEncoder::new(vec![]);
+        let _ = coder.analyze(&input[..]).unwrap();
+        coder.analyze_finish().unwrap();
+        let compressed_length_bits = coder.compress(&input[..]).unwrap();
+
+        // huffman can't spend less than a bit per symbol
+        assert!(compressed_length_bits < (input.len() as u64) / 10);
+    }
+
+    #[test]
+    fn adaptive_model_learns() {
+        let input = vec![b'z'; 10_000];
+
+        let mut coder: ArithmeticEncoder<_, AdaptiveModel> = ArithmeticEncoder::new(vec![]);
+        coder.analyze_finish().unwrap();
+        assert_eq!(0, coder.position());
+        let compressed_length_bits = coder.compress(&input[..]).unwrap();
+
+        assert!(compressed_length_bits < (input.len() as u64) / 10);
+    }
+
+    #[test]
+    fn not_analyzed_symbol() {
+        let mut coder: ArithmeticEncoder<_, StaticModel> = ArithmeticEncoder::new(vec![]);
+        let _ = coder.analyze(&b"ab"[..]).unwrap();
+        coder.analyze_finish().unwrap();
+        assert!(coder.compress(&b"abc"[..]).is_err());
+    }
+
+    #[test]
+    fn corrupted_header() {
+        let input = [0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let result: Result<ArithmeticDecoder<_, StaticModel>> =
+            ArithmeticDecoder::new(Cursor::new(&input[..]));
+        assert!(result.is_err());
+
+        // a single symbol with a zero frequency
+        let input = [0, 1, b'a', 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let result: Result<ArithmeticDecoder<_, StaticModel>> =
+            ArithmeticDecoder::new(Cursor::new(&input[..]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn compress_before_analyze_finish() {
+        let mut coder: ArithmeticEncoder<_, AdaptiveModel> = ArithmeticEncoder::new(vec![]);
+        let _ = coder.compress(&b"ab"[..]);
+    }
+
+    fn check_data<M: Model>(input_slice: &[u8]) -> bool {
+        check_multiple::<M>(vec![input_slice.to_vec()])
+    }
+
+    fn check_multiple<M: Model>(inputs: Vec<Vec<u8>>) -> bool {
+        let mut coder: ArithmeticEncoder<_, M> = ArithmeticEncoder::new(vec![]);
+
+        for i in &inputs {
+            let analyzed_length_bits = coder.analyze(i.as_slice()).unwrap();
+            assert_eq!(i.len() as u64 * 8, analyzed_length_bits);
+        }
+        coder.analyze_finish().unwrap();
+
+        let mut offsets = Vec::with_capacity(inputs.len());
+        for i in &inputs {
+            offsets.push(coder.position());
+            let _ = coder.compress(i.as_slice()).unwrap();
+        }
+        coder.compress_finish().unwrap();
+
+        let compressed = Cursor::new(coder.get_output_ref().as_slice());
+        let mut decoder: ArithmeticDecoder<_, M> = ArithmeticDecoder::new(compressed).unwrap();
+
+        for (i, &offset_bit) in inputs.iter().zip(offsets.iter()) {
+            let original_length_bits = i.len() as u64 * 8;
+            let mut decoded = Vec::with_capacity(i.len());
+            let decoded_length_bits = decoder.decode(&mut decoded, offset_bit, original_length_bits)
+                .unwrap();
+
+            if original_length_bits != decoded_length_bits || *i != decoded {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,214 @@
+use encoding::binary_to_text::INVALID_CHAR;
+use std::io::{Error, ErrorKind, Result};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Ascii85 {
+    // btoa-style, "z" abbreviates a group of four zero bytes
+    Plain,
+    // as Plain, framed with "<~" and "~>" like PostScript and PDF streams
+    Adobe,
+    // ZeroMQ's printable alphabet; partial groups are encoded like in Ascii85
+    Z85,
+}
+
+pub const ASCII85: Ascii85 = Ascii85::Plain;
+pub const ADOBE_ASCII85: Ascii85 = Ascii85::Adobe;
+pub const Z85: Ascii85 = Ascii85::Z85;
+
+impl Ascii85 {
+    pub fn encode(&self, data: &[u8]) -> String {
+        let table = self.encode_table();
+        let mut result = Vec::with_capacity((data.len() + 3) / 4 * 5 + 4);
+        if *self == Ascii85::Adobe {
+            result.extend_from_slice(b"<~");
+        }
+
+        for group in data.chunks(4) {
+            let n = group.iter()
+                .enumerate()
+                .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (24 - i * 8));
+            if n == 0 && group.len() == 4 && *self != Ascii85::Z85 {
+                result.push(b'z');
+                continue;
+            }
+
+            let mut chars = [0; 5];
+            let mut n = n;
+            for ch in chars.iter_mut().rev() {
+                *ch = table[(n % 85) as usize];
+                n /= 85;
+            }
+            result.extend_from_slice(&chars[..group.len() + 1]);
+        }
+
+        if *self == Ascii85::Adobe {
+            result.extend_from_slice(b"~>");
+        }
+        String::from_utf8(result).unwrap()
+    }
+
+    // whitespace is skipped; the "<~" and "~>" frame is optional for Plain and Adobe
+    pub fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let table = self.decode_table();
+        let mut input = input;
+        let mut offset = 0;
+        if *self != Ascii85::Z85 {
+            let trimmed = trim_whitespace(input);
+            if trimmed.starts_with(b"<~") {
+                offset = trimmed.as_ptr() as usize - input.as_ptr() as usize + 2;
+                input = &trimmed[2..];
+            }
+            let trimmed = trim_whitespace(input);
+            if trimmed.ends_with(b"~>") {
+                input = &trimmed[..trimmed.len() - 2];
+            }
+        }
+
+        let mut result = Vec::with_capacity(input.len() / 5 * 4 + 4);
+        let mut group = [0u8; 5];
+        let mut length = 0;
+        for (position, &ch) in input.iter().enumerate() {
+            if ch.is_ascii_whitespace() {
+                continue;
+            } else if ch == b'z' && length == 0 && *self != Ascii85::Z85 {
+                result.extend_from_slice(&[0; 4]);
+                continue;
+            }
+
+            let value = table[ch as usize];
+            if value == INVALID_CHAR {
+                let message = format!("Invalid ascii85 character 0x{:02x} at {}",
+                                      ch,
+                                      offset + position);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+            group[length] = value;
+            length += 1;
+            if length == 5 {
+                try!(decode_group(&group, 4, &mut result));
+                length = 0;
+            }
+        }
+
+        match length {
+            0 => Ok(result),
+            1 => Err(Error::new(ErrorKind::InvalidData, "Truncated ascii85 input")),
+            _ => {
+                // a partial group is padded with the largest digit
+                for value in &mut group[length..] {
+                    *value = 84;
+                }
+                try!(decode_group(&group, length - 1, &mut result));
+                Ok(result)
+            }
+        }
+    }
+
+    fn encode_table(&self) -> &'static [u8] {
+        match *self {
+            Ascii85::Plain | Ascii85::Adobe => ASCII85_ENCODE_TABLE,
+            Ascii85::Z85 => Z85_ENCODE_TABLE,
+        }
+    }
+
+    fn decode_table(&self) -> &'static [u8; 256] {
+        match *self {
+            Ascii85::Plain | Ascii85::Adobe => &ASCII85_DECODE_TABLE,
+            Ascii85::Z85 => &Z85_DECODE_TABLE,
+        }
+    }
+}
+
+fn decode_group(group: &[u8; 5], length: usize, output: &mut Vec<u8>) -> Result<()> {
+    let n = group.iter().fold(0u64, |n, &value| n * 85 + value as u64);
+    if n > u32::max_value() as u64 {
+        return Err(Error::new(ErrorKind::InvalidData, "Ascii85 group overflow"));
+    }
+    for i in 0..length {
+        output.push((n >> (24 - i * 8)) as u8);
+    }
+    Ok(())
+}
+
+fn trim_whitespace(input: &[u8]) -> &[u8] {
+    let start = input.iter().position(|ch| !ch.is_ascii_whitespace()).unwrap_or(input.len());
+    let end = input.iter().rposition(|ch| !ch.is_ascii_whitespace()).map_or(start, |i| i + 1);
+    &input[start..end]
+}
+
+static ASCII85_ENCODE_TABLE: &'static [u8] =
+    b"!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstu";
+
+static Z85_ENCODE_TABLE: &'static [u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+static ASCII85_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7,
+     8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+     32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54,
+     55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77,
+     78, 79, 80, 81, 82, 83, 84, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+static Z85_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 68, 255, 84, 83, 82, 72,
+     255, 75, 76, 70, 65, 255, 63, 62, 69, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 64, 255, 73, 66, 74, 71,
+     81, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57,
+     58, 59, 60, 61, 77, 255, 78, 67, 255, 255, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+     23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 79, 255, 80, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn random_roundtrip(data: Vec<u8>) -> bool {
+            [ASCII85, ADOBE_ASCII85, Z85].iter().all(|ascii85| {
+                ascii85.decode(ascii85.encode(&data[..]).as_bytes()).unwrap() == data
+            })
+        }
+    }
+
+    #[test]
+    fn ascii85() {
+        assert_eq!("", ASCII85.encode(b""));
+        assert_eq!("BOu!rDZ", ASCII85.encode(b"hello"));
+        assert_eq!("9jqo^BlbD-BleB1DJ+*+F(f,q", ASCII85.encode(b"Man is distinguished"));
+        assert_eq!("<~z!!~>", ADOBE_ASCII85.encode(&[0; 5]));
+        assert_eq!(b"hello".to_vec(), ASCII85.decode(b" <~BOu!\nrDZ~>\n").unwrap());
+        assert_eq!(vec![0; 5], ADOBE_ASCII85.decode(b"<~z!!~>").unwrap());
+    }
+
+    #[test]
+    fn z85() {
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!("HelloWorld", Z85.encode(&data));
+        assert_eq!(data.to_vec(), Z85.decode(b"HelloWorld").unwrap());
+        assert_eq!("00000", Z85.encode(&[0; 4]));
+    }
+
+    #[test]
+    fn invalid() {
+        let error = ASCII85.decode(b"BOu!v").unwrap_err();
+        assert_eq!("Invalid ascii85 character 0x76 at 4", error.to_string());
+        assert!(ASCII85.decode(b"BOu!rD").is_err());
+        assert!(ASCII85.decode(b"BOzu!").is_err());
+        assert!(ASCII85.decode(b"uuuuu").is_err());
+        assert!(Z85.decode(b"z").is_err());
+    }
+}
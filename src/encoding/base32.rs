@@ -0,0 +1,222 @@
+use encoding::binary_to_text::INVALID_CHAR;
+use std::io::{Error, ErrorKind, Result};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Alphabet {
+    Rfc4648,
+    Hex,
+    // excludes I, L, O and U; decoding maps them (and lowercase) to look-alike digits
+    Crockford,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Base32 {
+    pub alphabet: Alphabet,
+    pub padding: bool,
+}
+
+pub const BASE32: Base32 = Base32 {
+    alphabet: Alphabet::Rfc4648,
+    padding: true,
+};
+
+pub const BASE32_NO_PAD: Base32 = Base32 {
+    alphabet: Alphabet::Rfc4648,
+    padding: false,
+};
+
+pub const BASE32_HEX: Base32 = Base32 {
+    alphabet: Alphabet::Hex,
+    padding: true,
+};
+
+pub const CROCKFORD: Base32 = Base32 {
+    alphabet: Alphabet::Crockford,
+    padding: false,
+};
+
+impl Base32 {
+    pub fn encode(&self, data: &[u8]) -> String {
+        let table = encode_table(self.alphabet);
+        let mut result = Vec::with_capacity((data.len() + 4) / 5 * 8);
+
+        for group in data.chunks(5) {
+            let n = group.iter()
+                .enumerate()
+                .fold(0u64, |n, (i, &byte)| n | (byte as u64) << (32 - i * 8));
+            let chars = (group.len() * 8 + 4) / 5;
+
+            for i in 0..8 {
+                if i < chars {
+                    result.push(table[(n >> (35 - i * 5)) as usize & 31]);
+                } else if self.padding {
+                    result.push(b'=');
+                }
+            }
+        }
+
+        String::from_utf8(result).unwrap()
+    }
+
+    // lowercase, whitespace and missing padding are accepted
+    pub fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let table = decode_table(self.alphabet);
+        let mut result = Vec::with_capacity(input.len() / 8 * 5 + 5);
+        let mut buffer: u64 = 0;
+        let mut bits = 0;
+        let mut chars = 0;
+        let mut padding = false;
+
+        for (position, &ch) in input.iter().enumerate() {
+            let value = table[ch as usize];
+            if ch == b'=' {
+                padding = true;
+            } else if is_ignored(self.alphabet, ch) {
+                continue;
+            } else if value == INVALID_CHAR || padding {
+                let message = format!("Invalid base32 character 0x{:02x} at {}", ch, position);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            } else {
+                buffer = (buffer << 5) | value as u64;
+                bits += 5;
+                chars += 1;
+                if bits >= 8 {
+                    bits -= 8;
+                    result.push((buffer >> bits) as u8);
+                }
+            }
+        }
+
+        // 1, 3 and 6 characters can't end a group
+        match chars % 8 {
+            1 | 3 | 6 => Err(Error::new(ErrorKind::InvalidData, "Truncated base32 input")),
+            _ => Ok(result),
+        }
+    }
+}
+
+fn is_ignored(alphabet: Alphabet, ch: u8) -> bool {
+    ch.is_ascii_whitespace() || alphabet == Alphabet::Crockford && ch == b'-'
+}
+
+fn encode_table(alphabet: Alphabet) -> &'static [u8] {
+    match alphabet {
+        Alphabet::Rfc4648 => RFC4648_ENCODE_TABLE,
+        Alphabet::Hex => HEX_ENCODE_TABLE,
+        Alphabet::Crockford => CROCKFORD_ENCODE_TABLE,
+    }
+}
+
+fn decode_table(alphabet: Alphabet) -> &'static [u8; 256] {
+    match alphabet {
+        Alphabet::Rfc4648 => &RFC4648_DECODE_TABLE,
+        Alphabet::Hex => &HEX_DECODE_TABLE,
+        Alphabet::Crockford => &CROCKFORD_DECODE_TABLE,
+    }
+}
+
+static RFC4648_ENCODE_TABLE: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+static HEX_ENCODE_TABLE: &'static [u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+static CROCKFORD_ENCODE_TABLE: &'static [u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// the decode tables accept lowercase, Crockford's also maps O to 0 and I, L to 1
+static RFC4648_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 26, 27, 28, 29, 30, 31, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+     17, 18, 19, 20, 21, 22, 23, 24, 25, 255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8,
+     9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+static HEX_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 255, 255, 255,
+     255, 255, 255, 255, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+     28, 29, 30, 31, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 10, 11, 12, 13, 14, 15, 16,
+     17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+static CROCKFORD_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 255, 255, 255,
+     255, 255, 255, 255, 10, 11, 12, 13, 14, 15, 16, 17, 1, 18, 19, 1, 20, 21, 0, 22, 23, 24, 25,
+     26, 255, 27, 28, 29, 30, 31, 255, 255, 255, 255, 255, 255, 10, 11, 12, 13, 14, 15, 16, 17, 1,
+     18, 19, 1, 20, 21, 0, 22, 23, 24, 25, 26, 255, 27, 28, 29, 30, 31, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn random_roundtrip(data: Vec<u8>) -> bool {
+            [BASE32, BASE32_NO_PAD, BASE32_HEX, CROCKFORD].iter().all(|base32| {
+                let encoded = base32.encode(&data[..]);
+                base32.decode(encoded.as_bytes()).unwrap() == data &&
+                base32.decode(encoded.to_lowercase().as_bytes()).unwrap() == data
+            })
+        }
+    }
+
+    #[test]
+    fn rfc4648() {
+        let vectors = [("", "", ""),
+                       ("f", "MY======", "CO======"),
+                       ("fo", "MZXQ====", "CPNG===="),
+                       ("foo", "MZXW6===", "CPNMU==="),
+                       ("foob", "MZXW6YQ=", "CPNMUOG="),
+                       ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+                       ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======")];
+        for &(raw, encoded, hex) in &vectors {
+            assert_eq!(encoded, BASE32.encode(raw.as_bytes()));
+            assert_eq!(hex, BASE32_HEX.encode(raw.as_bytes()));
+            assert_eq!(encoded.trim_right_matches('='),
+                       BASE32_NO_PAD.encode(raw.as_bytes()));
+            assert_eq!(raw.as_bytes(), &BASE32.decode(encoded.as_bytes()).unwrap()[..]);
+            assert_eq!(raw.as_bytes(), &BASE32_HEX.decode(hex.as_bytes()).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn totp_secret() {
+        let secret = b"jbsw y3dp ehpk 3pxp";
+        assert_eq!(b"Hello!\xde\xad\xbe\xef".to_vec(), BASE32.decode(secret).unwrap());
+    }
+
+    #[test]
+    fn crockford() {
+        assert_eq!("CSQPYRK1", CROCKFORD.encode(b"fooba"));
+        assert_eq!(b"fooba".to_vec(), CROCKFORD.decode(b"csqp-yrki").unwrap());
+        assert_eq!(CROCKFORD.decode(b"0O1IL").unwrap(), CROCKFORD.decode(b"00111").unwrap());
+        assert!(CROCKFORD.decode(b"U").is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        let error = BASE32.decode(b"MZXW1").unwrap_err();
+        assert_eq!("Invalid base32 character 0x31 at 4", error.to_string());
+        assert!(BASE32.decode(b"MZX").is_err());
+        assert!(BASE32.decode(b"MY==MY==").is_err());
+    }
+}
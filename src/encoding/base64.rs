@@ -1,156 +1,563 @@
+use encoding::binary_to_text::INVALID_CHAR;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Alphabet {
+    Standard,
+    UrlSafe,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Config {
+    pub alphabet: Alphabet,
+    pub padding: bool,
+    // encoded lines are separated with "\r\n"
+    pub line_length: Option<usize>,
+    // rejects characters outside the alphabet and malformed padding instead of skipping them
+    pub strict: bool,
+}
+
+pub const STANDARD: Config = Config {
+    alphabet: Alphabet::Standard,
+    padding: true,
+    line_length: None,
+    strict: false,
+};
+
+pub const STANDARD_NO_PAD: Config = Config {
+    alphabet: Alphabet::Standard,
+    padding: false,
+    line_length: None,
+    strict: false,
+};
+
+pub const URL_SAFE: Config = Config {
+    alphabet: Alphabet::UrlSafe,
+    padding: true,
+    line_length: None,
+    strict: false,
+};
+
+pub const URL_SAFE_NO_PAD: Config = Config {
+    alphabet: Alphabet::UrlSafe,
+    padding: false,
+    line_length: None,
+    strict: false,
+};
+
+pub const MIME: Config = Config {
+    alphabet: Alphabet::Standard,
+    padding: true,
+    line_length: Some(76),
+    strict: false,
+};
+
+pub fn encode(config: Config, data: &[u8]) -> String {
+    let mut encoder = Encoder::new(config);
+    let mut result = Vec::with_capacity(data.len() / 3 * 4 + 4);
+    encoder.push(data, &mut result);
+    encoder.finish(&mut result);
+    String::from_utf8(result).unwrap()
+}
+
+pub fn decode<T>(config: Config, input: &T) -> Result<Vec<u8>>
+    where T: AsRef<[u8]> + ?Sized
+{
+    let input = input.as_ref();
+    let mut decoder = Decoder::new(config);
+    let mut result = Vec::with_capacity(input.len() / 4 * 3 + 3);
+    try!(decoder.push(input, &mut result));
+    try!(decoder.finish(&mut result));
+    Ok(result)
+}
+
 pub fn base64_encode<S>(input: S) -> String
     where S: Into<String>
 {
-    let input = input.into();
-    let data = string_to_vec(input.as_str());
-    let mut result: Vec<u8> = vec![];
+    encode(STANDARD, input.into().as_bytes())
+}
 
-    let n = input.len();
-    let mut i = 0;
-    while i < n {
-        let char_index = compute_char_index(&data[..], i);
-        let char_indexes = separate_24_to_6_bit(char_index);
+pub fn base64_decode<S>(input: S) -> String
+    where S: Into<String>
+{
+    let data = decode(STANDARD, &input.into()).unwrap();
+    String::from_utf8_lossy(&data[..]).into_owned()
+}
 
-        result.push(ENCODE_TABLE[char_indexes[0]]);
-        result.push(ENCODE_TABLE[char_indexes[1]]);
+struct Encoder {
+    config: Config,
+    pending: [u8; 3],
+    pending_length: usize,
+    column: usize,
+}
 
-        if i + 1 < n {
-            result.push(ENCODE_TABLE[char_indexes[2]]);
+impl Encoder {
+    fn new(config: Config) -> Self {
+        Encoder {
+            config: config,
+            pending: [0; 3],
+            pending_length: 0,
+            column: 0,
         }
+    }
 
-        if i + 2 < n {
-            result.push(ENCODE_TABLE[char_indexes[3]]);
+    fn push(&mut self, data: &[u8], output: &mut Vec<u8>) {
+        for &byte in data {
+            self.pending[self.pending_length] = byte;
+            self.pending_length += 1;
+            if self.pending_length == 3 {
+                let group = self.pending;
+                self.encode_group(&group, output);
+                self.pending_length = 0;
+            }
         }
+    }
 
-        i += 3;
+    fn finish(&mut self, output: &mut Vec<u8>) {
+        if self.pending_length > 0 {
+            let group = self.pending;
+            let length = self.pending_length;
+            self.encode_group(&group[..length], output);
+            self.pending_length = 0;
+        }
     }
 
-    let pads = n % 3;
-    if pads > 0 {
-        for _ in pads..3 {
-            result.push(b'=');
+    fn encode_group(&mut self, group: &[u8], output: &mut Vec<u8>) {
+        let table = encode_table(self.config.alphabet);
+        let n = group.iter()
+            .enumerate()
+            .fold(0, |n, (i, &byte)| n | (byte as usize) << (16 - i * 8));
+
+        for i in 0..4 {
+            if i <= group.len() {
+                self.emit(table[(n >> (18 - i * 6)) & 63], output);
+            } else if self.config.padding {
+                self.emit(b'=', output);
+            }
         }
     }
 
-    String::from_utf8(result).unwrap()
+    fn emit(&mut self, ch: u8, output: &mut Vec<u8>) {
+        if let Some(line_length) = self.config.line_length {
+            if self.column == line_length {
+                output.extend_from_slice(b"\r\n");
+                self.column = 0;
+            }
+            self.column += 1;
+        }
+        output.push(ch);
+    }
 }
 
-pub fn base64_decode<S>(input: S) -> String
-    where S: Into<String>
-{
-    let input = input.into();
-    let data = string_to_vec(input.as_str());
-    let mut result: Vec<u8> = vec![];
+struct Decoder {
+    config: Config,
+    buffer: u32,
+    count: usize,
+    padding: usize,
+    position: usize,
+}
 
-    let mut iter = 0;
-    let mut len = 0;
+impl Decoder {
+    fn new(config: Config) -> Self {
+        Decoder {
+            config: config,
+            buffer: 0,
+            count: 0,
+            padding: 0,
+            position: 0,
+        }
+    }
 
-    let mut buf: usize = 0;
-    let add_to_result = |result: &mut Vec<u8>, buf: &usize, bits: usize| {
-        let value = (buf >> bits) & 255;
-        result.push(value as u8);
-    };
+    fn push(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<()> {
+        for &ch in input {
+            try!(self.push_char(ch, output));
+            self.position += 1;
+        }
+        Ok(())
+    }
 
-    let n = data.len();
-    let mut i = 0;
-    while i < n {
-        let c: u8 = DECODE_TABLE[data[i]];
-        i += 1;
+    fn push_char(&mut self, ch: u8, output: &mut Vec<u8>) -> Result<()> {
+        let strict = self.config.strict;
 
-        match c {
-            WHITESPACE => continue,
-            EQUALS => break,
-            _ => {
-                buf = (buf << 6) | (c as usize);
-                iter += 1;
-
-                if iter == 4 {
-                    len += 3;
-                    add_to_result(&mut result, &buf, 16);
-                    add_to_result(&mut result, &buf, 8);
-                    add_to_result(&mut result, &buf, 0);
-                    buf = 0;
-                    iter = 0;
+        if ch == b'=' {
+            if !strict {
+                self.padding += 1;
+            } else if self.count < 2 || self.count + self.padding == 4 {
+                return Err(self.invalid_character(ch));
+            } else {
+                self.padding += 1;
+            }
+            return Ok(());
+        }
+
+        if ch == b'\r' || ch == b'\n' {
+            if strict && self.config.line_length.is_none() {
+                return Err(self.invalid_character(ch));
+            }
+            return Ok(());
+        }
+
+        match decode_char(self.config.alphabet, ch) {
+            Some(value) => {
+                // padded groups may be concatenated
+                if self.padding > 0 {
+                    if strict && self.count + self.padding != 4 {
+                        return Err(self.invalid_character(ch));
+                    }
+                    try!(self.finish(output));
                 }
+                self.buffer = (self.buffer << 6) | value as u32;
+                self.count += 1;
+                if self.count == 4 {
+                    output.push((self.buffer >> 16) as u8);
+                    output.push((self.buffer >> 8) as u8);
+                    output.push(self.buffer as u8);
+                    self.buffer = 0;
+                    self.count = 0;
+                }
+                Ok(())
+            }
+            None if strict => Err(self.invalid_character(ch)),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<()> {
+        let (trailing_bits, expected_padding) = match self.count {
+            0 => (0, 0),
+            1 if self.config.strict => {
+                return Err(Error::new(ErrorKind::InvalidData, "Truncated base64 input"));
+            }
+            1 => (0, 0),
+            2 => {
+                output.push((self.buffer >> 4) as u8);
+                (self.buffer & 0xf, 2)
+            }
+            _ => {
+                output.push((self.buffer >> 10) as u8);
+                output.push((self.buffer >> 2) as u8);
+                (self.buffer & 0x3, 1)
+            }
+        };
+
+        if self.config.strict {
+            if trailing_bits != 0 {
+                return Err(Error::new(ErrorKind::InvalidData, "Non-zero trailing bits"));
+            }
+            if self.config.padding && self.padding != expected_padding ||
+               !self.config.padding && self.padding != 0 {
+                return Err(Error::new(ErrorKind::InvalidData, "Invalid base64 padding"));
             }
         }
+
+        self.buffer = 0;
+        self.count = 0;
+        self.padding = 0;
+        Ok(())
     }
 
-    if iter == 3 {
-        len += 2;
-        add_to_result(&mut result, &buf, 10);
-        add_to_result(&mut result, &buf, 2);
-    } else if iter == 2 {
-        len += 1;
-        add_to_result(&mut result, &buf, 4);
+    fn invalid_character(&self, ch: u8) -> Error {
+        let message = format!("Invalid base64 character 0x{:02x} at {}", ch, self.position);
+        Error::new(ErrorKind::InvalidData, message)
     }
+}
 
-    result.truncate(len);
-    String::from_utf8(result).unwrap()
+// encodes everything written to it; the last group is written by finish or on drop
+pub struct Base64Writer<W>
+    where W: Write
+{
+    output: W,
+    encoder: Encoder,
+    buffer: Vec<u8>,
 }
 
-fn compute_char_index(data: &[usize], i: usize) -> usize {
-    let n = data.len();
+impl<W> Base64Writer<W>
+    where W: Write
+{
+    pub fn new(output: W, config: Config) -> Self {
+        Base64Writer {
+            output: output,
+            encoder: Encoder::new(config),
+            buffer: vec![],
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.output
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
 
-    let mut char_index = data[i] << 16;
+    pub fn finish(&mut self) -> Result<()> {
+        self.buffer.clear();
+        self.encoder.finish(&mut self.buffer);
+        try!(self.output.write_all(&self.buffer[..]));
+        self.output.flush()
+    }
+}
 
-    if i + 1 < n {
-        char_index += data[i + 1] << 8;
+impl<W> Write for Base64Writer<W>
+    where W: Write
+{
+    fn write(&mut self, data: &[u8]) -> Result<usize> {
+        self.buffer.clear();
+        self.encoder.push(data, &mut self.buffer);
+        try!(self.output.write_all(&self.buffer[..]));
+        Ok(data.len())
     }
 
-    if i + 2 < n {
-        char_index += data[i + 2];
+    // an incomplete group can't be flushed before finish
+    fn flush(&mut self) -> Result<()> {
+        self.output.flush()
     }
+}
 
-    char_index
+impl<W> Drop for Base64Writer<W>
+    where W: Write
+{
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
 }
 
-fn separate_24_to_6_bit(n: usize) -> [usize; 4] {
-    [(n >> 18) & 63, (n >> 12) & 63, (n >> 6) & 63, n & 63]
+pub struct Base64Reader<R>
+    where R: Read
+{
+    input: R,
+    decoder: Decoder,
+    buffer: Vec<u8>,
+    buffer_offset: usize,
+    finished: bool,
 }
 
-fn string_to_vec(input: &str) -> Vec<usize> {
-    let mut data: Vec<usize> = vec![];
-    for i in input.as_bytes() {
-        data.push(*i as usize);
+impl<R> Base64Reader<R>
+    where R: Read
+{
+    pub fn new(input: R, config: Config) -> Self {
+        Base64Reader {
+            input: input,
+            decoder: Decoder::new(config),
+            buffer: vec![],
+            buffer_offset: 0,
+            finished: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.input
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.input
+    }
+
+    fn fill_buffer(&mut self) -> Result<()> {
+        let mut input = [0; 4096];
+        self.buffer.clear();
+        self.buffer_offset = 0;
+
+        while self.buffer.is_empty() && !self.finished {
+            let length = try!(self.input.read(&mut input));
+            if length == 0 {
+                self.finished = true;
+                try!(self.decoder.finish(&mut self.buffer));
+            } else {
+                try!(self.decoder.push(&input[..length], &mut self.buffer));
+            }
+        }
+        Ok(())
     }
-    data
 }
 
+impl<R> Read for Base64Reader<R>
+    where R: Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.buffer_offset == self.buffer.len() {
+            try!(self.fill_buffer());
+        }
+
+        let available = &self.buffer[self.buffer_offset..];
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.buffer_offset += length;
+        Ok(length)
+    }
+}
 
-const WHITESPACE: u8 = 64u8;
-const EQUALS: u8 = 65u8;
+fn encode_table(alphabet: Alphabet) -> &'static [u8] {
+    match alphabet {
+        Alphabet::Standard => STANDARD_ENCODE_TABLE,
+        Alphabet::UrlSafe => URL_SAFE_ENCODE_TABLE,
+    }
+}
 
-static DECODE_TABLE: [u8; 256] =
-    [66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 64, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66,
-     66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 62, 66, 66,
-     66, 63, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 66, 66, 66, 65, 66, 66, 66, 0, 1, 2, 3, 4, 5,
-     6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 66, 66, 66, 66,
-     66, 66, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46,
-     47, 48, 49, 50, 51, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66,
-     66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66,
-     66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66,
-     66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66,
-     66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66,
-     66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66, 66];
+fn decode_char(alphabet: Alphabet, ch: u8) -> Option<u8> {
+    let table = match alphabet {
+        Alphabet::Standard => &STANDARD_DECODE_TABLE,
+        Alphabet::UrlSafe => &URL_SAFE_DECODE_TABLE,
+    };
+    match table[ch as usize] {
+        INVALID_CHAR => None,
+        value => Some(value),
+    }
+}
 
-static ENCODE_TABLE: &'static [u8] =
+static STANDARD_ENCODE_TABLE: &'static [u8] =
     b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
+static URL_SAFE_ENCODE_TABLE: &'static [u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+static STANDARD_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 62, 255, 255, 255, 63, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 255,
+     255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
+     18, 19, 20, 21, 22, 23, 24, 25, 255, 255, 255, 255, 255, 255, 26, 27, 28, 29, 30, 31, 32, 33,
+     34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+static URL_SAFE_DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 62, 255, 255, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 255,
+     255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
+     18, 19, 20, 21, 22, 23, 24, 25, 255, 255, 255, 255, 63, 255, 26, 27, 28, 29, 30, 31, 32, 33,
+     34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
 #[cfg(test)]
 mod tests {
+    use std::io::{Cursor, Read, Write};
     use super::*;
 
     const RAW: &'static str = "Hello Rust! Привет Раст!\n";
     const ENCODED: &'static str = "SGVsbG8gUnVzdCEg0J/RgNC40LLQtdGCINCg0LDRgdGCIQo=";
 
+    const CONFIGS: [Config; 5] = [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD, MIME];
+
+    quickcheck! {
+        fn random_roundtrip(data: Vec<u8>) -> bool {
+            CONFIGS.iter().all(|&config| {
+                let strict = Config { strict: true, ..config };
+                let encoded = encode(config, &data[..]);
+                decode(config, &encoded).unwrap() == data &&
+                decode(strict, &encoded).unwrap() == data
+            })
+        }
+
+        fn random_streaming(data: Vec<u8>, chunk_size: usize) -> bool {
+            let chunk_size = chunk_size % 7 + 1;
+            CONFIGS.iter().all(|&config| {
+                let mut writer = Base64Writer::new(vec![], config);
+                for chunk in data.chunks(chunk_size) {
+                    writer.write_all(chunk).unwrap();
+                }
+                writer.finish().unwrap();
+                let encoded = writer.get_ref().clone();
+
+                let mut reader = Base64Reader::new(Cursor::new(&encoded[..]), config);
+                let mut decoded = vec![0; chunk_size];
+                let mut result = vec![];
+                loop {
+                    let length = reader.read(&mut decoded[..]).unwrap();
+                    if length == 0 {
+                        break;
+                    }
+                    result.extend_from_slice(&decoded[..length]);
+                }
+
+                encoded == encode(config, &data[..]).into_bytes() && result == data
+            })
+        }
+    }
+
     #[test]
-    fn encode() {
+    fn encode_string() {
         assert_eq!(ENCODED, base64_encode(RAW))
     }
 
     #[test]
-    fn decode() {
+    fn decode_string() {
         assert_eq!(RAW, base64_decode(ENCODED))
     }
+
+    #[test]
+    fn rfc4648() {
+        let vectors = [("", ""),
+                       ("f", "Zg=="),
+                       ("fo", "Zm8="),
+                       ("foo", "Zm9v"),
+                       ("foob", "Zm9vYg=="),
+                       ("fooba", "Zm9vYmE="),
+                       ("foobar", "Zm9vYmFy")];
+        for &(raw, encoded) in &vectors {
+            assert_eq!(encoded, encode(STANDARD, raw.as_bytes()));
+            assert_eq!(encoded.trim_right_matches('='),
+                       encode(STANDARD_NO_PAD, raw.as_bytes()));
+            assert_eq!(raw.as_bytes(), &decode(STANDARD, encoded).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn alphabets() {
+        let data = [0xfb, 0xff, 0xbf];
+        assert_eq!("+/+/", encode(STANDARD, &data));
+        assert_eq!("-_-_", encode(URL_SAFE, &data));
+        assert_eq!("-_8", encode(URL_SAFE_NO_PAD, &data[..2]));
+        assert_eq!(vec![0xfb, 0xff], decode(URL_SAFE_NO_PAD, "-_8").unwrap());
+        assert!(decode(Config { strict: true, ..URL_SAFE }, "+/+/").is_err());
+    }
+
+    #[test]
+    fn mime() {
+        let data = vec![0; 60];
+        let encoded = encode(MIME, &data[..]);
+        assert_eq!(format!("{}\r\n{}", "A".repeat(76), "A".repeat(4)), encoded);
+        assert_eq!(data, decode(Config { strict: true, ..MIME }, &encoded).unwrap());
+        assert_eq!("", encode(MIME, &[]));
+    }
+
+    #[test]
+    fn strict() {
+        let strict = Config { strict: true, ..STANDARD };
+        assert_eq!(b"foo".to_vec(), decode(STANDARD, " Zm9v\n!").unwrap());
+        assert_eq!(b"f".to_vec(), decode(STANDARD, "Zg").unwrap());
+
+        let error = decode(strict, "Zm9v!").unwrap_err();
+        assert_eq!("Invalid base64 character 0x21 at 4", error.to_string());
+        assert!(decode(strict, "Zm9v\n").is_err());
+        assert!(decode(strict, "Zg").is_err());
+        assert!(decode(strict, "Zg=").is_err());
+        assert!(decode(strict, "Zg===").is_err());
+        assert!(decode(strict, "Zg=Zg==").is_err());
+        assert!(decode(strict, "Zh==").is_err());
+        assert!(decode(strict, "Z").is_err());
+        assert!(decode(Config { strict: true, ..STANDARD_NO_PAD }, "Zg==").is_err());
+        assert_eq!(b"f".to_vec(), decode(strict, "Zg==").unwrap());
+    }
+
+    #[test]
+    fn concatenated() {
+        let strict = Config { strict: true, ..STANDARD };
+        assert_eq!(b"ff".to_vec(), decode(strict, "Zg==Zg==").unwrap());
+        assert_eq!(b"ffoo".to_vec(), decode(strict, "Zg==Zm8=bw==").unwrap());
+        assert_eq!(b"ffo".to_vec(), decode(STANDARD, "Zg==Zm8=").unwrap());
+        assert_eq!(b"ff".to_vec(), decode(STANDARD, "Zg=Zg").unwrap());
+    }
 }
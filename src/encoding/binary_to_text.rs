@@ -0,0 +1,81 @@
+use encoding::ascii85::Ascii85;
+use encoding::base32::Base32;
+use encoding::base64;
+use encoding::hex::Hex;
+use std::io::Result;
+
+// binary-to-text encodings such as base64, base32, hex and ascii85
+pub trait BinaryToText {
+    fn encode(&self, data: &[u8]) -> String;
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>>;
+}
+
+// marks the bytes outside of an alphabet in decode tables
+pub const INVALID_CHAR: u8 = 0xff;
+
+impl BinaryToText for base64::Config {
+    fn encode(&self, data: &[u8]) -> String {
+        base64::encode(*self, data)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        base64::decode(*self, input)
+    }
+}
+
+impl BinaryToText for Base32 {
+    fn encode(&self, data: &[u8]) -> String {
+        Base32::encode(self, data)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Base32::decode(self, input)
+    }
+}
+
+impl BinaryToText for Hex {
+    fn encode(&self, data: &[u8]) -> String {
+        Hex::encode(self, data)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Hex::decode(self, input)
+    }
+}
+
+impl BinaryToText for Ascii85 {
+    fn encode(&self, data: &[u8]) -> String {
+        Ascii85::encode(self, data)
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        Ascii85::decode(self, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::ascii85::{ASCII85, Z85};
+    use encoding::base32::{BASE32, CROCKFORD};
+    use encoding::base64::{MIME, URL_SAFE_NO_PAD};
+    use encoding::hex::HEX;
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "mississippi river";
+
+    #[test]
+    fn round_trip() {
+        let encodings: Vec<Box<BinaryToText>> = vec![Box::new(MIME),
+                                                      Box::new(URL_SAFE_NO_PAD),
+                                                      Box::new(BASE32),
+                                                      Box::new(CROCKFORD),
+                                                      Box::new(HEX),
+                                                      Box::new(ASCII85),
+                                                      Box::new(Z85)];
+        for encoding in &encodings {
+            let encoded = encoding.encode(INPUT_TEXT.as_bytes());
+            assert_eq!(INPUT_TEXT.as_bytes(),
+                       &encoding.decode(encoded.as_bytes()).unwrap()[..]);
+        }
+    }
+}
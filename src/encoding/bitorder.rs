@@ -0,0 +1,11 @@
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BitOrder {
+    LsbFirst,
+    MsbFirst,
+}
+
+impl Default for BitOrder {
+    fn default() -> Self {
+        BitOrder::LsbFirst
+    }
+}
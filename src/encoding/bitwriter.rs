@@ -1,5 +1,7 @@
-use byteorder::{ByteOrder, BigEndian};
-use std::io::{Write, Result};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use encoding::bitorder::BitOrder;
+use std::cmp::min;
+use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
 use std::ops::Drop;
 
 pub struct BitWriter<W: Write> {
@@ -7,74 +9,261 @@ pub struct BitWriter<W: Write> {
     buffer: [u8; 1],
     position: u8,
     bytes_written: u64,
+    bit_order: BitOrder,
 }
 
 impl<W: Write> BitWriter<W> {
     pub fn new(output: W) -> Self {
+        Self::with_bit_order(output, BitOrder::LsbFirst)
+    }
+
+    pub fn with_bit_order(output: W, bit_order: BitOrder) -> Self {
         BitWriter {
             output: output,
             buffer: [0],
             position: 0,
             bytes_written: 0,
+            bit_order: bit_order,
         }
     }
 
+    pub fn bit_order(&self) -> BitOrder {
+        self.bit_order
+    }
+
     pub fn write_bit(&mut self, data: bool) -> Result<()> {
-        if data {
-            let shifted_one = 1 << self.position;
-            self.buffer[0] |= shifted_one;
+        self.write_bits(data as u64, 1)
+    }
+
+    // the full bytes are collected first and handed to the output in one call
+    pub fn write_bits(&mut self, data: u64, length: u8) -> Result<()> {
+        assert!(length <= 64);
+        if data & !mask(length) != 0 {
+            let message = format!("Value {} doesn't fit into {} bits", data, length);
+            return Err(Error::new(ErrorKind::InvalidInput, message));
         }
 
-        if self.position >= 7 {
-            self.position = 0;
-            try!(self.output.write_all(&self.buffer));
-            self.buffer[0] = 0;
-            self.bytes_written += 1;
-        } else {
-            self.position += 1;
+        let mut bytes = [0; 9];
+        let mut bytes_count = 0;
+        let mut written = 0;
+
+        while written < length {
+            let chunk_length = min(8 - self.position, length - written);
+            let (chunk, shift) = match self.bit_order {
+                BitOrder::LsbFirst => (data >> written, self.position),
+                BitOrder::MsbFirst => {
+                    (data >> (length - written - chunk_length),
+                     8 - self.position - chunk_length)
+                }
+            };
+            self.buffer[0] |= ((chunk & mask(chunk_length)) as u8) << shift;
+
+            written += chunk_length;
+            self.position += chunk_length;
+            if self.position == 8 {
+                bytes[bytes_count] = self.buffer[0];
+                bytes_count += 1;
+                self.buffer[0] = 0;
+                self.position = 0;
+            }
         }
 
+        try!(self.output.write_all(&bytes[..bytes_count]));
+        self.bytes_written += bytes_count as u64;
         Ok(())
     }
 
-    pub fn write_u8(&mut self, data: u8) -> Result<()> {
-        for i in 0..8 {
-            let shifted_one = 1 << i;
-            let d = (data & shifted_one) > 0;
-            try!(self.write_bit(d));
+    pub fn write_signed_bits(&mut self, data: i64, length: u8) -> Result<()> {
+        let shift = 64 - length as u32;
+        if length == 0 && data != 0 || length > 0 && ((data << shift) >> shift) != data {
+            let message = format!("Value {} doesn't fit into {} bits", data, length);
+            return Err(Error::new(ErrorKind::InvalidInput, message));
         }
 
-        Ok(())
+        self.write_bits(data as u64 & mask(length), length)
+    }
+
+    pub fn write_u8(&mut self, data: u8) -> Result<()> {
+        self.write_bits(data as u64, 8)
     }
 
     pub fn write_u16(&mut self, data: u16) -> Result<()> {
+        self.write_u16_be(data)
+    }
+
+    pub fn write_u32(&mut self, data: u32) -> Result<()> {
+        self.write_u32_be(data)
+    }
+
+    pub fn write_u64(&mut self, data: u64) -> Result<()> {
+        self.write_u64_be(data)
+    }
+
+    pub fn write_u16_be(&mut self, data: u16) -> Result<()> {
+        self.write_u16_with::<BigEndian>(data)
+    }
+
+    pub fn write_u16_le(&mut self, data: u16) -> Result<()> {
+        self.write_u16_with::<LittleEndian>(data)
+    }
+
+    pub fn write_u32_be(&mut self, data: u32) -> Result<()> {
+        self.write_u32_with::<BigEndian>(data)
+    }
+
+    pub fn write_u32_le(&mut self, data: u32) -> Result<()> {
+        self.write_u32_with::<LittleEndian>(data)
+    }
+
+    pub fn write_u64_be(&mut self, data: u64) -> Result<()> {
+        self.write_u64_with::<BigEndian>(data)
+    }
+
+    pub fn write_u64_le(&mut self, data: u64) -> Result<()> {
+        self.write_u64_with::<LittleEndian>(data)
+    }
+
+    pub fn write_f32_be(&mut self, data: f32) -> Result<()> {
+        self.write_f32_with::<BigEndian>(data)
+    }
+
+    pub fn write_f32_le(&mut self, data: f32) -> Result<()> {
+        self.write_f32_with::<LittleEndian>(data)
+    }
+
+    pub fn write_f64_be(&mut self, data: f64) -> Result<()> {
+        self.write_f64_with::<BigEndian>(data)
+    }
+
+    pub fn write_f64_le(&mut self, data: f64) -> Result<()> {
+        self.write_f64_with::<LittleEndian>(data)
+    }
+
+    pub fn write_u16_with<B: ByteOrder>(&mut self, data: u16) -> Result<()> {
         let mut buffer = [0; 2];
-        BigEndian::write_u16(&mut buffer, data);
-        for &i in &buffer {
-            try!(self.write_u8(i));
-        }
+        B::write_u16(&mut buffer, data);
+        self.write_byte_array(&buffer)
+    }
 
-        Ok(())
+    pub fn write_u32_with<B: ByteOrder>(&mut self, data: u32) -> Result<()> {
+        let mut buffer = [0; 4];
+        B::write_u32(&mut buffer, data);
+        self.write_byte_array(&buffer)
     }
 
-    pub fn write_u32(&mut self, data: u32) -> Result<()> {
+    pub fn write_u64_with<B: ByteOrder>(&mut self, data: u64) -> Result<()> {
+        let mut buffer = [0; 8];
+        B::write_u64(&mut buffer, data);
+        self.write_byte_array(&buffer)
+    }
+
+    pub fn write_f32_with<B: ByteOrder>(&mut self, data: f32) -> Result<()> {
         let mut buffer = [0; 4];
-        BigEndian::write_u32(&mut buffer, data);
-        for &i in &buffer {
-            try!(self.write_u8(i));
+        B::write_f32(&mut buffer, data);
+        self.write_byte_array(&buffer)
+    }
+
+    pub fn write_f64_with<B: ByteOrder>(&mut self, data: f64) -> Result<()> {
+        let mut buffer = [0; 8];
+        B::write_f64(&mut buffer, data);
+        self.write_byte_array(&buffer)
+    }
+
+    // aligned bytes go to the output directly
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<()> {
+        if self.position == 0 {
+            try!(self.output.write_all(data));
+            self.bytes_written += data.len() as u64;
+            return Ok(());
         }
 
+        for chunk in data.chunks(8) {
+            try!(self.write_byte_array(chunk));
+        }
         Ok(())
     }
 
-    pub fn write_u64(&mut self, data: u64) -> Result<()> {
-        let mut buffer = [0; 8];
-        BigEndian::write_u64(&mut buffer, data);
-        for &i in &buffer {
-            try!(self.write_u8(i));
+    pub fn align_to_byte(&mut self) -> Result<()> {
+        self.write_pending_byte()
+    }
+
+    pub fn bits_until_aligned(&self) -> u8 {
+        (8 - self.position) % 8
+    }
+
+    // 7 bits per byte, the high bit marks that more bytes follow
+    pub fn write_leb128(&mut self, data: u64) -> Result<()> {
+        let mut rest = data;
+        loop {
+            let byte = (rest & 0x7f) as u8;
+            rest >>= 7;
+            if rest == 0 {
+                return self.write_u8(byte);
+            }
+            try!(self.write_u8(byte | 0x80));
         }
+    }
 
-        Ok(())
+    pub fn write_signed_leb128(&mut self, data: i64) -> Result<()> {
+        let mut rest = data;
+        loop {
+            let byte = (rest & 0x7f) as u8;
+            rest >>= 7;
+            let sign_bit_clear = byte & 0x40 == 0;
+            if rest == 0 && sign_bit_clear || rest == -1 && !sign_bit_clear {
+                return self.write_u8(byte);
+            }
+            try!(self.write_u8(byte | 0x80));
+        }
+    }
+
+    // the codes below are defined bit by bit, their binary parts always start
+    // with the most significant bit regardless of the bit order
+    pub fn write_elias_gamma(&mut self, data: u64) -> Result<()> {
+        if data == 0 {
+            let e = Error::new(ErrorKind::InvalidInput, "Elias gamma can't encode zero");
+            return Err(e);
+        }
+
+        let length = bit_length(data);
+        try!(self.write_bits(0, length - 1));
+        self.write_bits_msb_first(data, length)
+    }
+
+    pub fn write_elias_delta(&mut self, data: u64) -> Result<()> {
+        if data == 0 {
+            let e = Error::new(ErrorKind::InvalidInput, "Elias delta can't encode zero");
+            return Err(e);
+        }
+
+        let length = bit_length(data);
+        try!(self.write_elias_gamma(length as u64));
+        self.write_bits_msb_first(data & mask(length - 1), length - 1)
+    }
+
+    // the quotient is written in unary as ones terminated by a zero
+    pub fn write_rice(&mut self, data: u64, k: u8) -> Result<()> {
+        assert!(k < 64);
+        let mut quotient = data >> k;
+        while quotient > 0 {
+            let length = min(quotient, 64) as u8;
+            try!(self.write_bits(mask(length), length));
+            quotient -= length as u64;
+        }
+        try!(self.write_bit(false));
+        self.write_bits_msb_first(data & mask(k), k)
+    }
+
+    pub fn write_exp_golomb(&mut self, data: u64, k: u8) -> Result<()> {
+        assert!(k < 64);
+        let quotient = data >> k;
+        if quotient == u64::max_value() {
+            let message = format!("Value {} is too large for Exp-Golomb", data);
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+
+        try!(self.write_elias_gamma(quotient + 1));
+        self.write_bits_msb_first(data & mask(k), k)
     }
 
     pub fn get_ref(&self) -> &W {
@@ -90,12 +279,69 @@ impl<W: Write> BitWriter<W> {
     }
 
     pub fn flush(&mut self) -> Result<()> {
+        try!(self.write_pending_byte());
+        self.output.flush()
+    }
+
+    // the bits of an incomplete byte are padded with zeros
+    fn write_pending_byte(&mut self) -> Result<()> {
         if self.position != 0 {
-            let _ = self.output.write(&self.buffer);
+            try!(self.output.write_all(&self.buffer));
+            self.bytes_written += 1;
+            self.buffer[0] = 0;
             self.position = 0;
         }
+        Ok(())
+    }
 
-        self.output.flush()
+    fn write_bits_msb_first(&mut self, data: u64, length: u8) -> Result<()> {
+        match self.bit_order {
+            BitOrder::LsbFirst => self.write_bits(reverse_bits(data, length), length),
+            BitOrder::MsbFirst => self.write_bits(data, length),
+        }
+    }
+
+    // each byte keeps the bit order of the writer
+    fn write_byte_array(&mut self, buffer: &[u8]) -> Result<()> {
+        let bytes = buffer.len();
+        let mut data = 0;
+        for (i, &byte) in buffer.iter().enumerate() {
+            let shift = match self.bit_order {
+                BitOrder::LsbFirst => i,
+                BitOrder::MsbFirst => bytes - 1 - i,
+            };
+            data |= (byte as u64) << (shift * 8);
+        }
+        self.write_bits(data, bytes as u8 * 8)
+    }
+}
+
+fn bit_length(data: u64) -> u8 {
+    64 - data.leading_zeros() as u8
+}
+
+fn reverse_bits(data: u64, length: u8) -> u64 {
+    if length == 0 {
+        0
+    } else {
+        data.reverse_bits() >> (64 - length)
+    }
+}
+
+fn mask(length: u8) -> u64 {
+    if length >= 64 {
+        !0
+    } else {
+        (1 << length) - 1
+    }
+}
+
+// pending bits are written out first, so the writer is always byte-aligned after seeking
+impl<W: Write + Seek> Seek for BitWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        try!(self.write_pending_byte());
+        self.bytes_written = try!(self.output.seek(pos));
+        Ok(self.bytes_written)
     }
 }
 
@@ -108,7 +354,8 @@ impl<T: Write> Drop for BitWriter<T> {
 #[cfg(test)]
 mod tests {
     use byteorder::{BigEndian, ReadBytesExt};
-    use std::io::Cursor;
+    use encoding::bitorder::BitOrder;
+    use std::io::{Cursor, Seek, SeekFrom};
     use std::mem;
     use super::*;
 
@@ -321,6 +568,190 @@ mod tests {
         assert_data(&[0b00001101, 0b00001000], &writer);
     }
 
+    #[test]
+    fn msb_first() {
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 3]), BitOrder::MsbFirst);
+        writer.write_bit(true).unwrap();
+        writer.write_bit(false).unwrap();
+        writer.write_bit(true).unwrap();
+        writer.write_u8(1).unwrap();
+        assert_bytes_written(1, &writer);
+        assert_data(&[0b10100000], &writer);
+        writer.write_bit(true).unwrap();
+        writer.flush().unwrap();
+        assert_data(&[0b10100000, 0b00110000], &writer);
+
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 2]), BitOrder::MsbFirst);
+        writer.write_u8(0x8c).unwrap();
+        writer.write_u8(0x2d).unwrap();
+        assert_data(&[0x8c, 0x2d], &writer);
+    }
+
+    #[test]
+    fn write_bits() {
+        let mut writer = new_writer(2);
+        writer.write_bits(5, 3).unwrap();
+        writer.write_bits(88, 9).unwrap();
+        writer.write_bits(8, 4).unwrap();
+        writer.write_bits(0, 0).unwrap();
+        assert_data(&[0b11000101, 0b10000010], &writer);
+
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 9]), BitOrder::MsbFirst);
+        writer.write_bits(0xf, 4).unwrap();
+        writer.write_bits(0xf010_2030_4050_6070, 64).unwrap();
+        writer.write_bits(8, 4).unwrap();
+        assert_data(&[0xff, 1, 2, 3, 4, 5, 6, 7, 8], &writer);
+    }
+
+    #[test]
+    fn write_signed_bits() {
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 2]), BitOrder::MsbFirst);
+        writer.write_signed_bits(-1, 3).unwrap();
+        writer.write_signed_bits(1, 5).unwrap();
+        writer.write_signed_bits(-8, 4).unwrap();
+        writer.write_signed_bits(0, 0).unwrap();
+        writer.write_signed_bits(i64::min_value(), 64).unwrap();
+        assert_eq!(76, writer.position());
+    }
+
+    #[test]
+    fn value_too_large() {
+        let mut writer = new_writer(1);
+        assert!(writer.write_bits(8, 3).is_err());
+        assert!(writer.write_bits(1, 0).is_err());
+        assert!(writer.write_signed_bits(4, 3).is_err());
+        assert!(writer.write_signed_bits(-5, 3).is_err());
+        assert!(writer.write_signed_bits(-1, 0).is_err());
+        assert_eq!(0, writer.position());
+    }
+
+    #[test]
+    fn byte_orders() {
+        let mut writer = new_writer(14);
+        writer.write_u16_le(0x0102).unwrap();
+        writer.write_u32_le(0x03040506).unwrap();
+        writer.write_u32_be(0x0708090a).unwrap();
+        writer.write_u16_be(0x0b0c).unwrap();
+        writer.write_u8(0x0d).unwrap();
+        writer.write_u8(0x0e).unwrap();
+        assert_data(&[2, 1, 6, 5, 4, 3, 7, 8, 9, 10, 11, 12, 13, 14], &writer);
+
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 8]), BitOrder::MsbFirst);
+        writer.write_u64_le(0x0102030405060708).unwrap();
+        assert_data(&[8, 7, 6, 5, 4, 3, 2, 1], &writer);
+    }
+
+    #[test]
+    fn floats() {
+        let mut writer = new_writer(12);
+        writer.write_f32_be(1.5).unwrap();
+        writer.write_f64_le(-2.0).unwrap();
+        assert_data(&[0x3f, 0xc0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xc0], &writer);
+    }
+
+    #[test]
+    fn leb128() {
+        let mut writer = new_writer(6);
+        writer.write_leb128(624485).unwrap();
+        writer.write_signed_leb128(-123456).unwrap();
+        assert_data(&[0xe5, 0x8e, 0x26, 0xc0, 0xbb, 0x78], &writer);
+    }
+
+    #[test]
+    fn universal_codes() {
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 3]), BitOrder::MsbFirst);
+        writer.write_elias_gamma(5).unwrap();
+        writer.write_rice(9, 2).unwrap();
+        writer.write_exp_golomb(3, 0).unwrap();
+        writer.write_elias_delta(10).unwrap();
+        writer.flush().unwrap();
+        assert_data(&[0b00101110, 0b01001000, 0b01000100], &writer);
+
+        let mut writer = new_writer(3);
+        writer.write_elias_gamma(5).unwrap();
+        writer.write_rice(9, 2).unwrap();
+        writer.write_exp_golomb(3, 0).unwrap();
+        writer.write_elias_delta(10).unwrap();
+        writer.flush().unwrap();
+        assert_data(&[0b01110100, 0b00010010, 0b00100010], &writer);
+
+        assert!(writer.write_elias_gamma(0).is_err());
+        assert!(writer.write_elias_delta(0).is_err());
+        assert!(writer.write_exp_golomb(u64::max_value(), 0).is_err());
+    }
+
+    #[test]
+    fn seek() {
+        let mut writer = BitWriter::new(Cursor::new(vec![]));
+        writer.write_u16(0x0102).unwrap();
+        writer.write_bits(5, 3).unwrap();
+
+        assert_eq!(1, writer.seek(SeekFrom::Start(1)).unwrap());
+        assert_eq!(8, writer.position());
+        writer.write_u8(9).unwrap();
+        assert_eq!(3, writer.seek(SeekFrom::End(0)).unwrap());
+        writer.write_u8(7).unwrap();
+        assert_eq!(2, writer.seek(SeekFrom::Current(-2)).unwrap());
+        writer.write_bit(false).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(24, writer.position());
+        assert_eq!(&[1, 9, 0, 7], writer.get_ref().get_ref().as_slice());
+
+        assert!(writer.seek(SeekFrom::Current(-4)).is_err());
+    }
+
+    #[test]
+    fn seek_mid_stream() {
+        let mut cursor = Cursor::new(vec![0; 100]);
+        cursor.seek(SeekFrom::End(0)).unwrap();
+        let mut writer = BitWriter::new(cursor);
+        writer.write_u32(0x01020304).unwrap();
+        assert_eq!(102, writer.seek(SeekFrom::Current(-2)).unwrap());
+        writer.write_u8(9).unwrap();
+        writer.flush().unwrap();
+
+        let data = writer.get_ref().get_ref();
+        assert_eq!(104, data.len());
+        assert_eq!(&[1, 2, 9, 4], &data[100..]);
+    }
+
+    #[test]
+    fn align_to_byte() {
+        let mut writer = new_writer(3);
+        writer.align_to_byte().unwrap();
+        assert_eq!(0, writer.position());
+        assert_eq!(0, writer.bits_until_aligned());
+
+        writer.write_bits(5, 3).unwrap();
+        assert_eq!(5, writer.bits_until_aligned());
+        writer.align_to_byte().unwrap();
+        assert_eq!(8, writer.position());
+        assert_eq!(0, writer.bits_until_aligned());
+        writer.write_u8(7).unwrap();
+        writer.write_bit(true).unwrap();
+        writer.align_to_byte().unwrap();
+        assert_data(&[5, 7, 1], &writer);
+    }
+
+    #[test]
+    fn write_bytes() {
+        let mut writer = new_writer(12);
+        writer.write_bytes(&[1, 2]).unwrap();
+        assert_bytes_written(2, &writer);
+        writer.write_bits(1, 4).unwrap();
+        writer.write_bytes(&[0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23]).unwrap();
+        writer.write_bits(0, 4).unwrap();
+        assert_eq!(96, writer.position());
+        assert_data(&[1, 2, 0x31, 0x52, 0x74, 0x96, 0xb8, 0xda, 0xfc, 0x1e, 0x30, 0x02],
+                    &writer);
+
+        let mut writer = BitWriter::with_bit_order(Cursor::new(vec![0; 3]), BitOrder::MsbFirst);
+        writer.write_bits(1, 4).unwrap();
+        writer.write_bytes(&[0x23, 0x45]).unwrap();
+        writer.write_bits(6, 4).unwrap();
+        assert_data(&[0x12, 0x34, 0x56], &writer);
+    }
+
     type MockWriter = BitWriter<Cursor<Vec<u8>>>;
 
     fn new_writer(size: usize) -> MockWriter {
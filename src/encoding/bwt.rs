@@ -0,0 +1,369 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+pub const DEFAULT_BLOCK_SIZE: usize = 900_000;
+
+const RUN_LENGTH: usize = 4;
+const MAX_EXTRA_RUN: usize = 255;
+
+// suffixes are sorted by prefix doubling: on every pass the rank of a suffix
+// covers twice as many leading bytes as before
+pub fn suffix_array(input: &[u8]) -> Vec<usize> {
+    let length = input.len();
+    let mut result = (0..length).collect::<Vec<usize>>();
+    if length <= 1 {
+        return result;
+    }
+
+    let mut ranks = input.iter().map(|&byte| byte as usize).collect::<Vec<usize>>();
+    let mut next_ranks = vec![0; length];
+    let mut k = 1;
+
+    loop {
+        {
+            let key = |i: usize| (ranks[i], if i + k < length { ranks[i + k] + 1 } else { 0 });
+            result.sort_by_key(|&i| key(i));
+
+            next_ranks[result[0]] = 0;
+            for j in 1..length {
+                let step = if key(result[j - 1]) < key(result[j]) { 1 } else { 0 };
+                next_ranks[result[j]] = next_ranks[result[j - 1]] + step;
+            }
+        }
+
+        ::std::mem::swap(&mut ranks, &mut next_ranks);
+        if ranks[result[length - 1]] == length - 1 {
+            break;
+        }
+        k *= 2;
+    }
+
+    result
+}
+
+// the input is implicitly terminated by a sentinel smaller than any byte,
+// the returned index is the row where the sentinel was removed from the last column
+pub fn transform(input: &[u8]) -> (Vec<u8>, usize) {
+    let mut result = Vec::with_capacity(input.len());
+    let mut primary_index = 0;
+
+    if let Some(&last) = input.last() {
+        result.push(last);
+    }
+
+    for (row, &position) in suffix_array(input).iter().enumerate() {
+        if position == 0 {
+            primary_index = row + 1;
+        } else {
+            result.push(input[position - 1]);
+        }
+    }
+
+    (result, primary_index)
+}
+
+pub fn inverse_transform(input: &[u8], primary_index: usize) -> Result<Vec<u8>> {
+    if primary_index > input.len() || (primary_index == 0 && !input.is_empty()) {
+        let e = Error::new(ErrorKind::InvalidData, "Invalid primary index");
+        return Err(e);
+    }
+
+    // symbol 0 is the sentinel, bytes are shifted by one
+    let mut last_column = Vec::with_capacity(input.len() + 1);
+    last_column.extend(input[..primary_index].iter().map(|&byte| byte as usize + 1));
+    last_column.push(0);
+    last_column.extend(input[primary_index..].iter().map(|&byte| byte as usize + 1));
+
+    let mut starts = [0; 258];
+    for &symbol in &last_column {
+        starts[symbol + 1] += 1;
+    }
+    for symbol in 1..starts.len() {
+        starts[symbol] += starts[symbol - 1];
+    }
+
+    let mut next_rows = vec![0; last_column.len()];
+    for (row, &symbol) in last_column.iter().enumerate() {
+        next_rows[row] = starts[symbol];
+        starts[symbol] += 1;
+    }
+
+    let mut result = vec![0; input.len()];
+    let mut row = 0;
+    for i in (0..input.len()).rev() {
+        // reaching the sentinel early means the input isn't a transform output
+        if last_column[row] == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Invalid transform"));
+        }
+        result[i] = (last_column[row] - 1) as u8;
+        row = next_rows[row];
+    }
+
+    Ok(result)
+}
+
+pub fn move_to_front(input: &[u8]) -> Vec<u8> {
+    let mut alphabet = (0..256).map(|byte| byte as u8).collect::<Vec<u8>>();
+
+    input.iter()
+        .map(|&byte| {
+            let index = alphabet.iter().position(|&b| b == byte).unwrap();
+            alphabet.remove(index);
+            alphabet.insert(0, byte);
+            index as u8
+        })
+        .collect()
+}
+
+pub fn inverse_move_to_front(input: &[u8]) -> Vec<u8> {
+    let mut alphabet = (0..256).map(|byte| byte as u8).collect::<Vec<u8>>();
+
+    input.iter()
+        .map(|&index| {
+            let byte = alphabet.remove(index as usize);
+            alphabet.insert(0, byte);
+            byte
+        })
+        .collect()
+}
+
+// a run of 4 equal bytes is always followed by the count of further repeats
+pub fn run_length_encode(input: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(input.len());
+    let mut i = 0;
+
+    while i < input.len() {
+        let byte = input[i];
+        let run = input[i..]
+            .iter()
+            .take(RUN_LENGTH + MAX_EXTRA_RUN)
+            .take_while(|&&b| b == byte)
+            .count();
+
+        if run >= RUN_LENGTH {
+            result.extend_from_slice(&[byte; RUN_LENGTH]);
+            result.push((run - RUN_LENGTH) as u8);
+        } else {
+            result.extend_from_slice(&input[i..(i + run)]);
+        }
+        i += run;
+    }
+
+    result
+}
+
+pub fn run_length_decode(input: &[u8]) -> Result<Vec<u8>> {
+    let mut result = Vec::with_capacity(input.len());
+    let mut i = 0;
+
+    while i < input.len() {
+        let byte = input[i];
+        let run = input[i..].iter().take(RUN_LENGTH).take_while(|&&b| b == byte).count();
+        result.extend_from_slice(&input[i..(i + run)]);
+        i += run;
+
+        if run == RUN_LENGTH {
+            match input.get(i) {
+                Some(&count) => {
+                    for _ in 0..count {
+                        result.push(byte);
+                    }
+                    i += 1;
+                }
+                None => {
+                    let e = Error::new(ErrorKind::UnexpectedEof, "Missing run length");
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// every block is stored as its encoded length (u32), the primary index (u32)
+// and the run-length encoded move-to-front output of the transform
+pub fn encode<W>(input: &[u8], block_size: usize, output: &mut W) -> Result<()>
+    where W: Write
+{
+    assert!(block_size > 0);
+
+    for block in input.chunks(block_size) {
+        let (transformed, primary_index) = transform(block);
+        let data = run_length_encode(&move_to_front(&transformed[..])[..]);
+
+        try!(output.write_u32::<BigEndian>(data.len() as u32));
+        try!(output.write_u32::<BigEndian>(primary_index as u32));
+        try!(output.write_all(&data[..]));
+    }
+
+    Ok(())
+}
+
+pub fn decode<R>(mut input: R) -> Result<Vec<u8>>
+    where R: Read
+{
+    let mut result = vec![];
+
+    while let Some(length) = try!(read_block_length(&mut input)) {
+        let primary_index = try!(input.read_u32::<BigEndian>()) as usize;
+
+        // the length isn't trusted for the allocation
+        let mut data = vec![];
+        let _ = try!(input.by_ref().take(length as u64).read_to_end(&mut data));
+        if data.len() != length as usize {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Truncated block"));
+        }
+
+        let transformed = inverse_move_to_front(&try!(run_length_decode(&data[..]))[..]);
+        let block = try!(inverse_transform(&transformed[..], primary_index));
+        result.extend_from_slice(&block[..]);
+    }
+
+    Ok(result)
+}
+
+// returns None at the end of the stream, which may only come before a block
+fn read_block_length<R>(input: &mut R) -> Result<Option<u32>>
+    where R: Read
+{
+    let mut header = [0; 4];
+    let mut filled = 0;
+
+    while filled < header.len() {
+        match input.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(Error::new(ErrorKind::UnexpectedEof, "Truncated block header")),
+            Ok(length) => filled += length,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(Some(try!((&header[..]).read_u32::<BigEndian>())))
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::huffman::HuffmanEncoder;
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "mississippi river";
+
+    quickcheck! {
+        fn random_suffix_array(input: Vec<u8>) -> bool {
+            let result = suffix_array(&input[..]);
+            let mut expect = (0..input.len()).collect::<Vec<usize>>();
+            expect.sort_by_key(|&i| &input[i..]);
+            result == expect
+        }
+
+        fn random_transform(input: Vec<u8>) -> bool {
+            let (transformed, primary_index) = transform(&input[..]);
+            inverse_transform(&transformed[..], primary_index).unwrap() == input
+        }
+
+        fn random_move_to_front(input: Vec<u8>) -> bool {
+            inverse_move_to_front(&move_to_front(&input[..])[..]) == input
+        }
+
+        fn random_run_length(input: Vec<u8>, repeats: u16) -> bool {
+            let mut data = input.clone();
+            if let Some(&byte) = input.first() {
+                for _ in 0..(repeats % 600) {
+                    data.push(byte);
+                }
+            }
+            run_length_decode(&run_length_encode(&data[..])[..]).unwrap() == data
+        }
+
+        fn random_pipeline(input: Vec<u8>, block_size: u8) -> bool {
+            let mut data = vec![];
+            encode(&input[..], block_size as usize + 1, &mut data).unwrap();
+            decode(&data[..]).unwrap() == input
+        }
+    }
+
+    #[test]
+    fn known_transform() {
+        assert_eq!(vec![5, 3, 1, 0, 4, 2], suffix_array(b"banana"));
+        assert_eq!((b"annbaa".to_vec(), 4), transform(b"banana"));
+        assert_eq!(b"banana", inverse_transform(b"annbaa", 4).unwrap().as_slice());
+
+        assert_eq!((vec![], 0), transform(b""));
+        assert_eq!((vec![7], 1), transform(&[7]));
+        assert!(inverse_transform(b"", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_primary_index() {
+        assert!(inverse_transform(b"annbaa", 0).is_err());
+        assert!(inverse_transform(b"annbaa", 7).is_err());
+    }
+
+    #[test]
+    fn invalid_transform() {
+        assert_eq!(ErrorKind::InvalidData,
+                   inverse_transform(&[0, 0, 0], 1).unwrap_err().kind());
+        assert!(inverse_transform(b"banana", 2).is_err());
+    }
+
+    #[test]
+    fn known_move_to_front() {
+        assert_eq!(vec![98, 98, 110, 1, 1, 1, 0, 0], move_to_front(b"bananaaa"));
+        assert_eq!(b"bananaaa",
+                   inverse_move_to_front(&[98, 98, 110, 1, 1, 1, 0, 0]).as_slice());
+    }
+
+    #[test]
+    fn known_run_length() {
+        assert_eq!(vec![1, 2, 2, 2], run_length_encode(&[1, 2, 2, 2]));
+        assert_eq!(vec![2, 2, 2, 2, 0], run_length_encode(&[2, 2, 2, 2]));
+        assert_eq!(vec![2, 2, 2, 2, 3, 1], run_length_encode(&[2, 2, 2, 2, 2, 2, 2, 1]));
+
+        let long_run = vec![9; 600];
+        assert_eq!(vec![9, 9, 9, 9, 255, 9, 9, 9, 9, 255, 9, 9, 9, 9, 78],
+                   run_length_encode(&long_run[..]));
+        assert_eq!(long_run,
+                   run_length_decode(&run_length_encode(&long_run[..])[..]).unwrap());
+
+        assert!(run_length_decode(&[2, 2, 2, 2]).is_err());
+    }
+
+    #[test]
+    fn truncated_blocks() {
+        let mut data = vec![];
+        encode(INPUT_TEXT.as_bytes(), DEFAULT_BLOCK_SIZE, &mut data).unwrap();
+        assert!(decode(&data[..data.len() - 1]).is_err());
+        assert!(decode(&data[..6]).is_err());
+        assert!(decode(&[0, 0, 0][..]).is_err());
+        assert!(decode(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1, 0][..]).is_err());
+        assert!(decode(&[][..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn huffman_pipeline() {
+        let mut text = String::new();
+        for i in 0..300 {
+            text.push_str(&format!("{} {}\n", INPUT_TEXT, i));
+        }
+        let input_slice = text.as_bytes();
+
+        let mut encoded = vec![];
+        encode(input_slice, DEFAULT_BLOCK_SIZE, &mut encoded).unwrap();
+        assert_eq!(input_slice, decode(&encoded[..]).unwrap().as_slice());
+
+        let huffman_only_length = huffman_compressed_length(input_slice);
+        let pipeline_length = huffman_compressed_length(&encoded[..]);
+        assert!(pipeline_length * 3 < huffman_only_length);
+    }
+
+    fn huffman_compressed_length(input_slice: &[u8]) -> usize {
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(input_slice).unwrap();
+        coder.analyze_finish().unwrap();
+        let _ = coder.compress(input_slice).unwrap();
+        coder.compress_finish().unwrap();
+        coder.get_output_ref().len()
+    }
+}
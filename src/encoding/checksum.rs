@@ -0,0 +1,666 @@
+use encoding::crc::Crc;
+use std::io::{ErrorKind, Read, Result};
+
+// a streaming checksum; like std::hash::Hasher, finish doesn't consume the state
+pub trait Checksum {
+    fn update(&mut self, data: &[u8]);
+    fn finish(&self) -> u64;
+    fn reset(&mut self);
+    // the number of significant bits of finish()
+    fn width(&self) -> u8;
+
+    // returns the number of bytes read
+    fn update_from(&mut self, input: &mut Read) -> Result<u64> {
+        let mut buffer = [0; 8192];
+        let mut total = 0;
+        loop {
+            match input.read(&mut buffer) {
+                Ok(0) => return Ok(total),
+                Ok(length) => {
+                    self.update(&buffer[..length]);
+                    total += length as u64;
+                }
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Checksum for Crc {
+    fn update(&mut self, data: &[u8]) {
+        Crc::update(self, data);
+    }
+
+    fn finish(&self) -> u64 {
+        Crc::finish(self)
+    }
+
+    fn reset(&mut self) {
+        Crc::reset(self);
+    }
+
+    fn width(&self) -> u8 {
+        self.params().width
+    }
+}
+
+const ADLER_MODULO: u32 = 65_521;
+// the largest n such that 255n(n+1)/2 + (n+1)(ADLER_MODULO-1) fits in u32
+const ADLER_MAX_CHUNK: usize = 5552;
+
+#[derive(Clone, Debug)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Adler32 { a: 1, b: 0 }
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Adler32::new()
+    }
+}
+
+impl Checksum for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        // the modulo is only taken once per chunk
+        for chunk in data.chunks(ADLER_MAX_CHUNK) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER_MODULO;
+            self.b %= ADLER_MODULO;
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        ((self.b << 16) | self.a) as u64
+    }
+
+    fn reset(&mut self) {
+        *self = Adler32::new();
+    }
+
+    fn width(&self) -> u8 {
+        32
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Fletcher16 {
+    sum1: u16,
+    sum2: u16,
+}
+
+impl Fletcher16 {
+    pub fn new() -> Self {
+        Fletcher16 { sum1: 0, sum2: 0 }
+    }
+}
+
+impl Checksum for Fletcher16 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.sum1 = (self.sum1 + byte as u16) % 255;
+            self.sum2 = (self.sum2 + self.sum1) % 255;
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        ((self.sum2 << 8) | self.sum1) as u64
+    }
+
+    fn reset(&mut self) {
+        *self = Fletcher16::new();
+    }
+
+    fn width(&self) -> u8 {
+        16
+    }
+}
+
+// sums little-endian 16-bit words; an odd trailing byte is padded with zero
+#[derive(Clone, Debug, Default)]
+pub struct Fletcher32 {
+    sum1: u32,
+    sum2: u32,
+    pending: Option<u8>,
+}
+
+impl Fletcher32 {
+    pub fn new() -> Self {
+        Fletcher32 {
+            sum1: 0,
+            sum2: 0,
+            pending: None,
+        }
+    }
+
+    fn add_word(&mut self, word: u16) {
+        self.sum1 = (self.sum1 + word as u32) % 65_535;
+        self.sum2 = (self.sum2 + self.sum1) % 65_535;
+    }
+}
+
+impl Checksum for Fletcher32 {
+    fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+        if let Some(low) = self.pending.take() {
+            match data.split_first() {
+                Some((&high, rest)) => {
+                    self.add_word(((high as u16) << 8) | low as u16);
+                    data = rest;
+                }
+                None => {
+                    self.pending = Some(low);
+                    return;
+                }
+            }
+        }
+
+        for word in data.chunks(2) {
+            if word.len() == 2 {
+                self.add_word(((word[1] as u16) << 8) | word[0] as u16);
+            } else {
+                self.pending = Some(word[0]);
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let (mut sum1, mut sum2) = (self.sum1, self.sum2);
+        if let Some(low) = self.pending {
+            sum1 = (sum1 + low as u32) % 65_535;
+            sum2 = (sum2 + sum1) % 65_535;
+        }
+        ((sum2 << 16) | sum1) as u64
+    }
+
+    fn reset(&mut self) {
+        *self = Fletcher32::new();
+    }
+
+    fn width(&self) -> u8 {
+        32
+    }
+}
+
+const FNV32_OFFSET: u32 = 0x811c_9dc5;
+const FNV32_PRIME: u32 = 0x0100_0193;
+const FNV64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Clone, Debug)]
+pub struct Fnv1a32 {
+    hash: u32,
+}
+
+impl Fnv1a32 {
+    pub fn new() -> Self {
+        Fnv1a32 { hash: FNV32_OFFSET }
+    }
+}
+
+impl Default for Fnv1a32 {
+    fn default() -> Self {
+        Fnv1a32::new()
+    }
+}
+
+impl Checksum for Fnv1a32 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.hash = (self.hash ^ byte as u32).wrapping_mul(FNV32_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+
+    fn reset(&mut self) {
+        *self = Fnv1a32::new();
+    }
+
+    fn width(&self) -> u8 {
+        32
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Fnv1a64 {
+    hash: u64,
+}
+
+impl Fnv1a64 {
+    pub fn new() -> Self {
+        Fnv1a64 { hash: FNV64_OFFSET }
+    }
+}
+
+impl Default for Fnv1a64 {
+    fn default() -> Self {
+        Fnv1a64::new()
+    }
+}
+
+impl Checksum for Fnv1a64 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.hash = (self.hash ^ byte as u64).wrapping_mul(FNV64_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn reset(&mut self) {
+        *self = Fnv1a64::new();
+    }
+
+    fn width(&self) -> u8 {
+        64
+    }
+}
+
+const XXH32_PRIME_1: u32 = 0x9e37_79b1;
+const XXH32_PRIME_2: u32 = 0x85eb_ca77;
+const XXH32_PRIME_3: u32 = 0xc2b2_ae3d;
+const XXH32_PRIME_4: u32 = 0x27d4_eb2f;
+const XXH32_PRIME_5: u32 = 0x1656_67b1;
+
+#[derive(Clone, Debug)]
+pub struct XxHash32 {
+    seed: u32,
+    accumulators: [u32; 4],
+    // the tail of the input that doesn't fill a whole 16-byte stripe yet
+    buffer: [u8; 16],
+    buffer_length: usize,
+    total_length: u64,
+}
+
+impl XxHash32 {
+    pub fn new() -> Self {
+        XxHash32::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u32) -> Self {
+        XxHash32 {
+            seed: seed,
+            accumulators: [seed.wrapping_add(XXH32_PRIME_1).wrapping_add(XXH32_PRIME_2),
+                           seed.wrapping_add(XXH32_PRIME_2),
+                           seed,
+                           seed.wrapping_sub(XXH32_PRIME_1)],
+            buffer: [0; 16],
+            buffer_length: 0,
+            total_length: 0,
+        }
+    }
+
+    fn process_stripe(&mut self, stripe: &[u8]) {
+        for (i, accumulator) in self.accumulators.iter_mut().enumerate() {
+            *accumulator = xxh32_round(*accumulator, read_u32(&stripe[i * 4..]));
+        }
+    }
+}
+
+impl Default for XxHash32 {
+    fn default() -> Self {
+        XxHash32::new()
+    }
+}
+
+impl Checksum for XxHash32 {
+    fn update(&mut self, data: &[u8]) {
+        self.total_length += data.len() as u64;
+        let data = fill_stripe(&mut self.buffer, &mut self.buffer_length, data);
+        if self.buffer_length < 16 {
+            return;
+        }
+
+        let buffer = self.buffer;
+        self.process_stripe(&buffer);
+        self.buffer_length = 0;
+
+        for stripe in data.chunks(16) {
+            if stripe.len() == 16 {
+                self.process_stripe(stripe);
+            } else {
+                self.buffer[..stripe.len()].copy_from_slice(stripe);
+                self.buffer_length = stripe.len();
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.total_length >= 16 {
+            let v = &self.accumulators;
+            v[0].rotate_left(1)
+                .wrapping_add(v[1].rotate_left(7))
+                .wrapping_add(v[2].rotate_left(12))
+                .wrapping_add(v[3].rotate_left(18))
+        } else {
+            self.seed.wrapping_add(XXH32_PRIME_5)
+        };
+        hash = hash.wrapping_add(self.total_length as u32);
+
+        let mut tail = &self.buffer[..self.buffer_length];
+        while tail.len() >= 4 {
+            hash = hash.wrapping_add(read_u32(tail).wrapping_mul(XXH32_PRIME_3))
+                .rotate_left(17)
+                .wrapping_mul(XXH32_PRIME_4);
+            tail = &tail[4..];
+        }
+        for &byte in tail {
+            hash = hash.wrapping_add((byte as u32).wrapping_mul(XXH32_PRIME_5))
+                .rotate_left(11)
+                .wrapping_mul(XXH32_PRIME_1);
+        }
+
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(XXH32_PRIME_2);
+        hash ^= hash >> 13;
+        hash = hash.wrapping_mul(XXH32_PRIME_3);
+        hash ^= hash >> 16;
+        hash as u64
+    }
+
+    fn reset(&mut self) {
+        *self = XxHash32::with_seed(self.seed);
+    }
+
+    fn width(&self) -> u8 {
+        32
+    }
+}
+
+const XXH64_PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH64_PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH64_PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH64_PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const XXH64_PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+#[derive(Clone, Debug)]
+pub struct XxHash64 {
+    seed: u64,
+    accumulators: [u64; 4],
+    buffer: [u8; 32],
+    buffer_length: usize,
+    total_length: u64,
+}
+
+impl XxHash64 {
+    pub fn new() -> Self {
+        XxHash64::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        XxHash64 {
+            seed: seed,
+            accumulators: [seed.wrapping_add(XXH64_PRIME_1).wrapping_add(XXH64_PRIME_2),
+                           seed.wrapping_add(XXH64_PRIME_2),
+                           seed,
+                           seed.wrapping_sub(XXH64_PRIME_1)],
+            buffer: [0; 32],
+            buffer_length: 0,
+            total_length: 0,
+        }
+    }
+
+    fn process_stripe(&mut self, stripe: &[u8]) {
+        for (i, accumulator) in self.accumulators.iter_mut().enumerate() {
+            *accumulator = xxh64_round(*accumulator, read_u64(&stripe[i * 8..]));
+        }
+    }
+}
+
+impl Default for XxHash64 {
+    fn default() -> Self {
+        XxHash64::new()
+    }
+}
+
+impl Checksum for XxHash64 {
+    fn update(&mut self, data: &[u8]) {
+        self.total_length += data.len() as u64;
+        let data = fill_stripe(&mut self.buffer, &mut self.buffer_length, data);
+        if self.buffer_length < 32 {
+            return;
+        }
+
+        let buffer = self.buffer;
+        self.process_stripe(&buffer);
+        self.buffer_length = 0;
+
+        for stripe in data.chunks(32) {
+            if stripe.len() == 32 {
+                self.process_stripe(stripe);
+            } else {
+                self.buffer[..stripe.len()].copy_from_slice(stripe);
+                self.buffer_length = stripe.len();
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.total_length >= 32 {
+            let v = &self.accumulators;
+            let mut hash = v[0].rotate_left(1)
+                .wrapping_add(v[1].rotate_left(7))
+                .wrapping_add(v[2].rotate_left(12))
+                .wrapping_add(v[3].rotate_left(18));
+            for &accumulator in v {
+                hash = (hash ^ xxh64_round(0, accumulator))
+                    .wrapping_mul(XXH64_PRIME_1)
+                    .wrapping_add(XXH64_PRIME_4);
+            }
+            hash
+        } else {
+            self.seed.wrapping_add(XXH64_PRIME_5)
+        };
+        hash = hash.wrapping_add(self.total_length);
+
+        let mut tail = &self.buffer[..self.buffer_length];
+        while tail.len() >= 8 {
+            hash = (hash ^ xxh64_round(0, read_u64(tail)))
+                .rotate_left(27)
+                .wrapping_mul(XXH64_PRIME_1)
+                .wrapping_add(XXH64_PRIME_4);
+            tail = &tail[8..];
+        }
+        if tail.len() >= 4 {
+            hash = (hash ^ (read_u32(tail) as u64).wrapping_mul(XXH64_PRIME_1))
+                .rotate_left(23)
+                .wrapping_mul(XXH64_PRIME_2)
+                .wrapping_add(XXH64_PRIME_3);
+            tail = &tail[4..];
+        }
+        for &byte in tail {
+            hash = (hash ^ (byte as u64).wrapping_mul(XXH64_PRIME_5))
+                .rotate_left(11)
+                .wrapping_mul(XXH64_PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(XXH64_PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(XXH64_PRIME_3);
+        hash ^= hash >> 32;
+        hash
+    }
+
+    fn reset(&mut self) {
+        *self = XxHash64::with_seed(self.seed);
+    }
+
+    fn width(&self) -> u8 {
+        64
+    }
+}
+
+fn xxh32_round(accumulator: u32, lane: u32) -> u32 {
+    accumulator.wrapping_add(lane.wrapping_mul(XXH32_PRIME_2))
+        .rotate_left(13)
+        .wrapping_mul(XXH32_PRIME_1)
+}
+
+fn xxh64_round(accumulator: u64, lane: u64) -> u64 {
+    accumulator.wrapping_add(lane.wrapping_mul(XXH64_PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH64_PRIME_1)
+}
+
+// appends as much of data as fits to the stripe buffer and returns the rest
+fn fill_stripe<'a>(buffer: &mut [u8], buffer_length: &mut usize, data: &'a [u8]) -> &'a [u8] {
+    let length = (buffer.len() - *buffer_length).min(data.len());
+    buffer[*buffer_length..*buffer_length + length].copy_from_slice(&data[..length]);
+    *buffer_length += length;
+    &data[length..]
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    (0..4).fold(0, |result, i| result | (data[i] as u32) << (i * 8))
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    (0..8).fold(0, |result, i| result | (data[i] as u64) << (i * 8))
+}
+
+pub const NAMES: &'static [&'static str] = &["adler32", "fletcher16", "fletcher32", "fnv1a32",
+                                             "fnv1a64", "xxh32", "xxh64", "crc32", "crc32c",
+                                             "crc64"];
+
+// looks up a checksum by its command line name
+pub fn by_name(name: &str) -> Option<Box<Checksum>> {
+    use encoding::crc::{CRC_32C, CRC_32_ISO_HDLC, CRC_64_XZ};
+
+    let checksum: Box<Checksum> = match name {
+        "adler32" => Box::new(Adler32::new()),
+        "fletcher16" => Box::new(Fletcher16::new()),
+        "fletcher32" => Box::new(Fletcher32::new()),
+        "fnv1a32" => Box::new(Fnv1a32::new()),
+        "fnv1a64" => Box::new(Fnv1a64::new()),
+        "xxh32" => Box::new(XxHash32::new()),
+        "xxh64" => Box::new(XxHash64::new()),
+        "crc32" => Box::new(Crc::new(CRC_32_ISO_HDLC)),
+        "crc32c" => Box::new(Crc::new(CRC_32C)),
+        "crc64" => Box::new(Crc::new(CRC_64_XZ)),
+        _ => return None,
+    };
+    Some(checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use test::Bencher;
+
+    quickcheck! {
+        fn random_streaming(data: Vec<u8>, split: usize) -> bool {
+            let split = if data.is_empty() { 0 } else { split % data.len() };
+            NAMES.iter().all(|name| {
+                let mut whole = by_name(name).unwrap();
+                whole.update(&data[..]);
+
+                let mut parts = by_name(name).unwrap();
+                parts.update(&data[..split]);
+                parts.update(&[]);
+                for chunk in data[split..].chunks(3) {
+                    parts.update(chunk);
+                }
+                whole.finish() == parts.finish()
+            })
+        }
+    }
+
+    #[test]
+    fn adler32() {
+        assert_eq!(1, checksum(Adler32::new(), b""));
+        assert_eq!(0x11e6_0398, checksum(Adler32::new(), b"Wikipedia"));
+
+        // long enough to exercise the delayed modulo
+        let data = vec![0xff; 20_000];
+        let (mut a, mut b) = (1, 0);
+        for &byte in &data {
+            a = (a + byte as u64) % 65_521;
+            b = (b + a) % 65_521;
+        }
+        assert_eq!((b << 16) | a, checksum(Adler32::new(), &data[..]));
+    }
+
+    #[test]
+    fn fletcher() {
+        assert_eq!(0xc8f0, checksum(Fletcher16::new(), b"abcde"));
+        assert_eq!(0x2057, checksum(Fletcher16::new(), b"abcdef"));
+        assert_eq!(0xf04f_c729, checksum(Fletcher32::new(), b"abcde"));
+        assert_eq!(0x5650_2d2a, checksum(Fletcher32::new(), b"abcdef"));
+        assert_eq!(0xebe1_9591, checksum(Fletcher32::new(), b"abcdefgh"));
+    }
+
+    #[test]
+    fn fnv1a() {
+        assert_eq!(0x811c_9dc5, checksum(Fnv1a32::new(), b""));
+        assert_eq!(0xe40c_292c, checksum(Fnv1a32::new(), b"a"));
+        assert_eq!(0xbf9c_f968, checksum(Fnv1a32::new(), b"foobar"));
+        assert_eq!(0xcbf2_9ce4_8422_2325, checksum(Fnv1a64::new(), b""));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, checksum(Fnv1a64::new(), b"a"));
+        assert_eq!(0x8594_4171_f739_67e8, checksum(Fnv1a64::new(), b"foobar"));
+    }
+
+    #[test]
+    fn xxhash() {
+        let long = b"Nobody inspects the spammish repetition";
+        assert_eq!(0x02cc_5d05, checksum(XxHash32::new(), b""));
+        assert_eq!(0x550d_7456, checksum(XxHash32::new(), b"a"));
+        assert_eq!(0x32d1_53ff, checksum(XxHash32::new(), b"abc"));
+        assert_eq!(0xe229_3b2f, checksum(XxHash32::new(), long));
+        assert_eq!(0xef46_db37_51d8_e999, checksum(XxHash64::new(), b""));
+        assert_eq!(0xd24e_c4f1_a98c_6e5b, checksum(XxHash64::new(), b"a"));
+        assert_eq!(0x44bc_2cf5_ad77_0999, checksum(XxHash64::new(), b"abc"));
+        assert_eq!(0xfbce_a83c_8a37_8bf1, checksum(XxHash64::new(), long));
+    }
+
+    #[test]
+    fn reset_and_seed() {
+        let mut hash = XxHash64::with_seed(1);
+        hash.update(b"abc");
+        let seeded = hash.finish();
+        assert!(seeded != checksum(XxHash64::new(), b"abc"));
+
+        hash.update(b"def");
+        hash.reset();
+        hash.update(b"abc");
+        assert_eq!(seeded, hash.finish());
+    }
+
+    #[test]
+    fn update_from() {
+        let data = vec![0x5a; 20_000];
+        let mut hash = XxHash32::new();
+        assert_eq!(20_000, hash.update_from(&mut Cursor::new(&data[..])).unwrap());
+        assert_eq!(checksum(XxHash32::new(), &data[..]), hash.finish());
+        assert_eq!(32, hash.width());
+    }
+
+    #[bench]
+    fn bench_xxh64(b: &mut Bencher) {
+        let data = vec![0x5a; 1 << 20];
+        b.bytes = data.len() as u64;
+        b.iter(|| checksum(XxHash64::new(), &data[..]));
+    }
+
+    fn checksum<C: Checksum>(checksum: C, data: &[u8]) -> u64 {
+        let mut checksum = checksum;
+        checksum.update(data);
+        checksum.finish()
+    }
+}
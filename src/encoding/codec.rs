@@ -0,0 +1,257 @@
+use encoding::arithmetic::{ArithmeticDecoder, ArithmeticEncoder, Model};
+use encoding::bitreader::BitReader;
+use encoding::bitwriter::BitWriter;
+use encoding::huffman::{HuffmanDecoder, HuffmanEncoder};
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+// files written before the codec was stored start with an entry count or an image width,
+// whose high byte is never 0x89, and are all Huffman
+const HEADER_MAGIC: u32 = 0x8945_4e43;
+const HEADER_VERSION: u8 = 1;
+
+pub const HEADER_LENGTH: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Codec {
+    Huffman,
+    Arithmetic,
+    AdaptiveArithmetic,
+}
+
+pub trait EntropyEncoder {
+    fn analyze<R: Read>(&mut self, input: R) -> Result<u64>;
+    fn analyze_finish(&mut self) -> Result<()>;
+    fn compress<R: Read>(&mut self, input: R) -> Result<u64>;
+    fn compress_finish(&mut self) -> Result<()>;
+    fn position(&self) -> u64;
+}
+
+pub trait EntropyDecoder {
+    fn decode(&mut self,
+              output: &mut Write,
+              offset_bit: u64,
+              original_length_bits: u64)
+              -> Result<u64>;
+    fn data_offset_bit(&self) -> u64;
+}
+
+impl Codec {
+    pub fn from_name(name: &str) -> Option<Codec> {
+        match name {
+            "huffman" => Some(Codec::Huffman),
+            "arithmetic" => Some(Codec::Arithmetic),
+            "adaptive" => Some(Codec::AdaptiveArithmetic),
+            _ => None,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Codec> {
+        match id {
+            0 => Some(Codec::Huffman),
+            1 => Some(Codec::Arithmetic),
+            2 => Some(Codec::AdaptiveArithmetic),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match *self {
+            Codec::Huffman => 0,
+            Codec::Arithmetic => 1,
+            Codec::AdaptiveArithmetic => 2,
+        }
+    }
+
+    pub fn write_header<W: Write>(&self, output: &mut BitWriter<W>) -> Result<()> {
+        try!(output.write_u32(HEADER_MAGIC));
+        try!(output.write_u8(HEADER_VERSION));
+        output.write_u8(self.id())
+    }
+
+    // inputs without a header are left as they are and read as Huffman
+    pub fn read_header<R: Read + Seek>(input: &mut BitReader<R>) -> Result<Codec> {
+        if try!(input.read_u32()) != HEADER_MAGIC {
+            try!(input.seek_bits(SeekFrom::Current(-32)));
+            return Ok(Codec::Huffman);
+        }
+
+        if try!(input.read_u8()) != HEADER_VERSION {
+            let e = Error::new(ErrorKind::InvalidData, "Unsupported header version");
+            return Err(e);
+        }
+
+        match Codec::from_id(try!(input.read_u8())) {
+            Some(codec) => Ok(codec),
+            None => {
+                let e = Error::new(ErrorKind::InvalidData, "Unknown entropy coder");
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<W: Write> EntropyEncoder for HuffmanEncoder<W> {
+    fn analyze<R: Read>(&mut self, input: R) -> Result<u64> {
+        HuffmanEncoder::analyze(self, input)
+    }
+
+    fn analyze_finish(&mut self) -> Result<()> {
+        HuffmanEncoder::analyze_finish(self)
+    }
+
+    fn compress<R: Read>(&mut self, input: R) -> Result<u64> {
+        HuffmanEncoder::compress(self, input)
+    }
+
+    fn compress_finish(&mut self) -> Result<()> {
+        HuffmanEncoder::compress_finish(self)
+    }
+
+    fn position(&self) -> u64 {
+        HuffmanEncoder::position(self)
+    }
+}
+
+impl<W: Write, M: Model> EntropyEncoder for ArithmeticEncoder<W, M> {
+    fn analyze<R: Read>(&mut self, input: R) -> Result<u64> {
+        ArithmeticEncoder::analyze(self, input)
+    }
+
+    fn analyze_finish(&mut self) -> Result<()> {
+        ArithmeticEncoder::analyze_finish(self)
+    }
+
+    fn compress<R: Read>(&mut self, input: R) -> Result<u64> {
+        ArithmeticEncoder::compress(self, input)
+    }
+
+    fn compress_finish(&mut self) -> Result<()> {
+        ArithmeticEncoder::compress_finish(self)
+    }
+
+    fn position(&self) -> u64 {
+        ArithmeticEncoder::position(self)
+    }
+}
+
+impl<R: Read + Seek> EntropyDecoder for HuffmanDecoder<R> {
+    fn decode(&mut self,
+              output: &mut Write,
+              offset_bit: u64,
+              original_length_bits: u64)
+              -> Result<u64> {
+        HuffmanDecoder::decode(self, output, offset_bit, original_length_bits)
+    }
+
+    fn data_offset_bit(&self) -> u64 {
+        HuffmanDecoder::data_offset_bit(self)
+    }
+}
+
+impl<R: Read + Seek, M: Model> EntropyDecoder for ArithmeticDecoder<R, M> {
+    fn decode(&mut self,
+              output: &mut Write,
+              offset_bit: u64,
+              original_length_bits: u64)
+              -> Result<u64> {
+        ArithmeticDecoder::decode(self, output, offset_bit, original_length_bits)
+    }
+
+    fn data_offset_bit(&self) -> u64 {
+        ArithmeticDecoder::data_offset_bit(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::arithmetic::{AdaptiveModel, ArithmeticDecoder, ArithmeticEncoder, StaticModel};
+    use encoding::bitreader::BitReader;
+    use encoding::bitwriter::BitWriter;
+    use encoding::huffman::{HuffmanDecoder, HuffmanEncoder};
+    use std::io::Cursor;
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "mississippi river";
+
+    #[test]
+    fn ids() {
+        for codec in &[Codec::Huffman, Codec::Arithmetic, Codec::AdaptiveArithmetic] {
+            assert_eq!(Some(*codec), Codec::from_id(codec.id()));
+        }
+        assert_eq!(None, Codec::from_id(3));
+
+        assert_eq!(Some(Codec::Arithmetic), Codec::from_name("arithmetic"));
+        assert_eq!(None, Codec::from_name("lzw"));
+    }
+
+    #[test]
+    fn header() {
+        for codec in &[Codec::Huffman, Codec::Arithmetic, Codec::AdaptiveArithmetic] {
+            let mut data = vec![];
+            {
+                let mut writer = BitWriter::new(&mut data);
+                codec.write_header(&mut writer).unwrap();
+                writer.write_u8(7).unwrap();
+            }
+            assert_eq!(HEADER_LENGTH + 1, data.len());
+
+            let mut reader = BitReader::new(Cursor::new(&data[..]));
+            assert_eq!(*codec, Codec::read_header(&mut reader).unwrap());
+            assert_eq!(7, reader.read_u8().unwrap());
+        }
+
+        let data = [0x89, 0x45, 0x4e, 0x43, 2, 0];
+        assert!(Codec::read_header(&mut BitReader::new(Cursor::new(&data[..]))).is_err());
+        let data = [0x89, 0x45, 0x4e, 0x43, 1, 3];
+        assert!(Codec::read_header(&mut BitReader::new(Cursor::new(&data[..]))).is_err());
+    }
+
+    #[test]
+    fn headerless_huffman() {
+        // written before the codec was stored
+        let data = [0x00, 0x08, 0x00, 0x09, 0x01, 0x76, 0x00, 0x0e, 0x01, 0x73, 0x00, 0x1f,
+                    0x01, 0x20, 0x00, 0x04, 0x01, 0x69, 0x00, 0x17, 0x01, 0x6d, 0x00, 0x0a,
+                    0x01, 0x72, 0x00, 0x0d, 0x01, 0x65, 0x00, 0x0b, 0x01, 0x70, 0x87, 0x8d,
+                    0xcd, 0xc6, 0x8b, 0x54];
+        let mut reader = BitReader::new(Cursor::new(&data[..]));
+        assert_eq!(Codec::Huffman, Codec::read_header(&mut reader).unwrap());
+        assert_eq!(0, reader.position());
+
+        let decoder = HuffmanDecoder::new(reader.into_inner().unwrap()).unwrap();
+        assert_eq!(INPUT_TEXT.as_bytes(), decode(decoder, INPUT_TEXT.len()).as_slice());
+    }
+
+    #[test]
+    fn generic_round_trip() {
+        let input_slice = INPUT_TEXT.as_bytes();
+
+        let mut data = vec![];
+        compress(HuffmanEncoder::new(&mut data, 1), input_slice);
+        let decoder = HuffmanDecoder::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(input_slice, decode(decoder, input_slice.len()).as_slice());
+
+        let mut data = vec![];
+        compress(ArithmeticEncoder::<_, StaticModel>::new(&mut data), input_slice);
+        let decoder = ArithmeticDecoder::<_, StaticModel>::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(input_slice, decode(decoder, input_slice.len()).as_slice());
+
+        let mut data = vec![];
+        compress(ArithmeticEncoder::<_, AdaptiveModel>::new(&mut data), input_slice);
+        let decoder = ArithmeticDecoder::<_, AdaptiveModel>::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(input_slice, decode(decoder, input_slice.len()).as_slice());
+    }
+
+    fn compress<E: EntropyEncoder>(mut encoder: E, input_slice: &[u8]) {
+        let _ = encoder.analyze(input_slice).unwrap();
+        encoder.analyze_finish().unwrap();
+        let _ = encoder.compress(input_slice).unwrap();
+        encoder.compress_finish().unwrap();
+    }
+
+    fn decode<D: EntropyDecoder>(mut decoder: D, length: usize) -> Vec<u8> {
+        let mut output = vec![];
+        let offset_bit = decoder.data_offset_bit();
+        let _ = decoder.decode(&mut output, offset_bit, length as u64 * 8).unwrap();
+        output
+    }
+}
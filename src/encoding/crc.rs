@@ -1,3 +1,5 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::io::{Result, Write};
 use std::mem;
 
 pub type T = u32;
@@ -60,9 +62,237 @@ fn leading_one(data: T) -> T {
     unreachable!()
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CrcParams {
+    pub width: u8,
+    pub polynomial: u64,
+    pub init: u64,
+    pub reflect_in: bool,
+    pub reflect_out: bool,
+    pub xor_out: u64,
+    pub check: u64,
+}
+
+// parameters follow the catalogue at http://reveng.sourceforge.net/crc-catalogue/,
+// `check` is the checksum of "123456789"
+pub const CRC_8_SMBUS: CrcParams = CrcParams {
+    width: 8,
+    polynomial: 0x07,
+    init: 0,
+    reflect_in: false,
+    reflect_out: false,
+    xor_out: 0,
+    check: 0xf4,
+};
+
+pub const CRC_16_CCITT: CrcParams = CrcParams {
+    width: 16,
+    polynomial: 0x1021,
+    init: 0,
+    reflect_in: true,
+    reflect_out: true,
+    xor_out: 0,
+    check: 0x2189,
+};
+
+pub const CRC_16_IBM_3740: CrcParams = CrcParams {
+    width: 16,
+    polynomial: 0x1021,
+    init: 0xffff,
+    reflect_in: false,
+    reflect_out: false,
+    xor_out: 0,
+    check: 0x29b1,
+};
+
+pub const CRC_32_ISO_HDLC: CrcParams = CrcParams {
+    width: 32,
+    polynomial: 0x04c1_1db7,
+    init: 0xffff_ffff,
+    reflect_in: true,
+    reflect_out: true,
+    xor_out: 0xffff_ffff,
+    check: 0xcbf4_3926,
+};
+
+pub const CRC_32C: CrcParams = CrcParams {
+    width: 32,
+    polynomial: 0x1edc_6f41,
+    init: 0xffff_ffff,
+    reflect_in: true,
+    reflect_out: true,
+    xor_out: 0xffff_ffff,
+    check: 0xe306_9283,
+};
+
+pub const CRC_32_BZIP2: CrcParams = CrcParams {
+    width: 32,
+    polynomial: 0x04c1_1db7,
+    init: 0xffff_ffff,
+    reflect_in: false,
+    reflect_out: false,
+    xor_out: 0xffff_ffff,
+    check: 0xfc89_1918,
+};
+
+pub const CRC_64_XZ: CrcParams = CrcParams {
+    width: 64,
+    polynomial: 0x42f0_e1eb_a9ea_3693,
+    init: 0xffff_ffff_ffff_ffff,
+    reflect_in: true,
+    reflect_out: true,
+    xor_out: 0xffff_ffff_ffff_ffff,
+    check: 0x995d_c9bb_df19_39fa,
+};
+
+const TABLES_COUNT: usize = 8;
+
+type Table = [u64; 256];
+
+// reflected CRCs keep the register in the low bits and shift it right,
+// the others keep it in the high bits and shift it left
+pub struct Crc {
+    params: CrcParams,
+    tables: Vec<Table>,
+    register: u64,
+}
+
+impl Crc {
+    pub fn new(params: CrcParams) -> Self {
+        assert!(params.width > 0 && params.width <= 64);
+        let mut result = Crc {
+            params: params,
+            tables: make_tables(&params),
+            register: 0,
+        };
+        result.reset();
+        result
+    }
+
+    pub fn checksum(params: CrcParams, data: &[u8]) -> u64 {
+        let mut crc = Self::new(params);
+        crc.update(data);
+        crc.finish()
+    }
+
+    pub fn params(&self) -> &CrcParams {
+        &self.params
+    }
+
+    pub fn reset(&mut self) {
+        self.register = if self.params.reflect_in {
+            reflect(self.params.init, self.params.width)
+        } else {
+            self.params.init << (64 - self.params.width)
+        };
+    }
+
+    // slice-by-8: table `i` holds the CRCs of single bytes followed by `i` zero bytes
+    pub fn update(&mut self, data: &[u8]) {
+        let mut register = self.register;
+        let tables = &self.tables[..];
+        let chunks_length = data.len() / TABLES_COUNT * TABLES_COUNT;
+
+        for chunk in data[..chunks_length].chunks(TABLES_COUNT) {
+            let word = if self.params.reflect_in {
+                register ^ LittleEndian::read_u64(chunk)
+            } else {
+                register ^ BigEndian::read_u64(chunk)
+            };
+
+            register = 0;
+            for (i, table) in tables.iter().enumerate() {
+                let shift = if self.params.reflect_in { 56 - i * 8 } else { i * 8 };
+                register ^= table[((word >> shift) & 0xff) as usize];
+            }
+        }
+
+        for &byte in &data[chunks_length..] {
+            register = if self.params.reflect_in {
+                tables[0][((register ^ byte as u64) & 0xff) as usize] ^ (register >> 8)
+            } else {
+                tables[0][((register >> 56) ^ byte as u64) as usize] ^ (register << 8)
+            };
+        }
+
+        self.register = register;
+    }
+
+    pub fn finish(&self) -> u64 {
+        let width = self.params.width;
+        let value = if self.params.reflect_in {
+            reflect(self.register, width)
+        } else {
+            self.register >> (64 - width)
+        };
+        let value = if self.params.reflect_out {
+            reflect(value, width)
+        } else {
+            value
+        };
+        value ^ self.params.xor_out
+    }
+}
+
+impl Write for Crc {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn make_tables(params: &CrcParams) -> Vec<Table> {
+    let width = params.width;
+    let mut first = [0; 256];
+
+    for (byte, entry) in first.iter_mut().enumerate() {
+        let mut register = byte as u64;
+        if params.reflect_in {
+            let polynomial = reflect(params.polynomial, width);
+            for _ in 0..8 {
+                register = (register >> 1) ^ if register & 1 > 0 { polynomial } else { 0 };
+            }
+        } else {
+            let polynomial = params.polynomial << (64 - width);
+            register <<= 56;
+            for _ in 0..8 {
+                let top_bit = register >> 63;
+                register = (register << 1) ^ if top_bit > 0 { polynomial } else { 0 };
+            }
+        }
+        *entry = register;
+    }
+
+    let mut result = vec![first];
+    for i in 1..TABLES_COUNT {
+        let mut table = [0; 256];
+        for byte in 0..256 {
+            let previous = result[i - 1][byte];
+            table[byte] = if params.reflect_in {
+                (previous >> 8) ^ first[(previous & 0xff) as usize]
+            } else {
+                (previous << 8) ^ first[(previous >> 56) as usize]
+            };
+        }
+        result.push(table);
+    }
+
+    result
+}
+
+fn reflect(data: u64, width: u8) -> u64 {
+    data.reverse_bits() >> (64 - width)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io;
     use super::*;
+    use test::Bencher;
 
     #[test]
     fn simple() {
@@ -98,6 +328,84 @@ mod tests {
         }
     }
 
+    quickcheck! {
+        fn random_streaming(data: Vec<u8>, split: usize) -> bool {
+            let split = if data.is_empty() { 0 } else { split % data.len() };
+            PRESETS.iter().all(|&params| {
+                let mut crc = Crc::new(params);
+                crc.update(&data[..split]);
+                crc.update(&data[split..]);
+                crc.finish() == bitwise_crc(&params, &data[..])
+            })
+        }
+    }
+
+    #[test]
+    fn presets() {
+        for params in PRESETS {
+            assert_eq!(params.check, Crc::checksum(*params, b"123456789"));
+            assert_eq!(params.check, bitwise_crc(params, b"123456789"));
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let mut crc = Crc::new(CRC_32_ISO_HDLC);
+        io::copy(&mut &b"123456789"[..], &mut crc).unwrap();
+        assert_eq!(CRC_32_ISO_HDLC.check, crc.finish());
+
+        crc.reset();
+        assert_eq!(0, crc.finish());
+        crc.update(b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(0x414f_a339, crc.finish());
+    }
+
+    #[bench]
+    fn bench_crc_32(b: &mut Bencher) {
+        let data = vec![0x5a; 1 << 20];
+        let mut crc = Crc::new(CRC_32_ISO_HDLC);
+        b.bytes = data.len() as u64;
+        b.iter(|| {
+            crc.update(&data[..]);
+            crc.finish()
+        })
+    }
+
+    const PRESETS: &'static [CrcParams] = &[CRC_8_SMBUS,
+                                            CRC_16_CCITT,
+                                            CRC_16_IBM_3740,
+                                            CRC_32_ISO_HDLC,
+                                            CRC_32C,
+                                            CRC_32_BZIP2,
+                                            CRC_64_XZ];
+
+    // the textbook algorithm, one bit at a time
+    fn bitwise_crc(params: &CrcParams, data: &[u8]) -> u64 {
+        let width = params.width as u32;
+        let top_bit = 1 << (width - 1);
+        let register_mask = if width == 64 { !0 } else { (1 << width) - 1 };
+        let mut register = params.init;
+
+        for &byte in data {
+            let byte = if params.reflect_in { byte.reverse_bits() } else { byte };
+            for i in (0..8).rev() {
+                let bit = ((byte >> i) & 1) as u64;
+                let feedback = (register & top_bit > 0) as u64 ^ bit;
+                register = (register << 1) & register_mask;
+                if feedback > 0 {
+                    register ^= params.polynomial;
+                }
+            }
+        }
+
+        let register = if params.reflect_out {
+            register.reverse_bits() >> (64 - width)
+        } else {
+            register
+        };
+        register ^ params.xor_out
+    }
+
     #[test]
     fn leading_one() {
         assert_eq!(3, super::leading_one(0b101));
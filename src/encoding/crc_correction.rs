@@ -0,0 +1,224 @@
+use encoding::crc::{Crc, CrcParams};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+// bits are numbered as `byte * 8 + bit`, where bit 0 is the least significant one;
+// the bits of the checksum follow the data bits
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ErrorPattern {
+    Single(usize),
+    Double(usize, usize),
+    Ambiguous,
+}
+
+// CRCs are linear over the flipped bits, so the difference between the computed
+// and the received checksum (the syndrome) depends only on the error positions
+pub struct CrcCorrector {
+    params: CrcParams,
+    data_length: usize,
+    syndromes: HashMap<u64, ErrorPattern>,
+}
+
+impl CrcCorrector {
+    pub fn new(params: CrcParams, data_length: usize, max_errors: u8) -> Self {
+        assert!(max_errors == 1 || max_errors == 2);
+
+        let single_syndromes = single_bit_syndromes(&params, data_length);
+        let mut syndromes = HashMap::with_capacity(single_syndromes.len());
+        for (position, &syndrome) in single_syndromes.iter().enumerate() {
+            let pattern = if syndromes.contains_key(&syndrome) {
+                ErrorPattern::Ambiguous
+            } else {
+                ErrorPattern::Single(position)
+            };
+            syndromes.insert(syndrome, pattern);
+        }
+
+        // a single-bit error is more likely than a double-bit one with the same syndrome
+        if max_errors == 2 {
+            let mut double_syndromes = HashMap::new();
+            for (first, &first_syndrome) in single_syndromes.iter().enumerate() {
+                let rest = single_syndromes.iter().enumerate().skip(first + 1);
+                for (second, &second_syndrome) in rest {
+                    let syndrome = first_syndrome ^ second_syndrome;
+                    if syndromes.contains_key(&syndrome) || syndrome == 0 {
+                        continue;
+                    }
+                    let pattern = if double_syndromes.contains_key(&syndrome) {
+                        ErrorPattern::Ambiguous
+                    } else {
+                        ErrorPattern::Double(first, second)
+                    };
+                    double_syndromes.insert(syndrome, pattern);
+                }
+            }
+            syndromes.extend(double_syndromes);
+        }
+
+        CrcCorrector {
+            params: params,
+            data_length: data_length,
+            syndromes: syndromes,
+        }
+    }
+
+    pub fn locate_errors(&self, data: &[u8], checksum: u64) -> Result<Vec<usize>> {
+        if data.len() != self.data_length {
+            let message = format!("Expected {} bytes, got {}", self.data_length, data.len());
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+
+        let syndrome = Crc::checksum(self.params, data) ^ checksum;
+        if syndrome == 0 {
+            return Ok(vec![]);
+        }
+
+        match self.syndromes.get(&syndrome) {
+            Some(&ErrorPattern::Single(position)) => Ok(vec![position]),
+            Some(&ErrorPattern::Double(first, second)) => Ok(vec![first, second]),
+            Some(&ErrorPattern::Ambiguous) |
+            None => Err(Error::new(ErrorKind::InvalidData, "Uncorrectable error")),
+        }
+    }
+
+    // returns the corrected data and checksum
+    pub fn correct(&self, data: &[u8], checksum: u64) -> Result<(Vec<u8>, u64)> {
+        let mut data = data.to_vec();
+        let mut checksum = checksum;
+        let data_bits = self.data_length * 8;
+
+        for position in try!(self.locate_errors(&data[..], checksum)) {
+            if position < data_bits {
+                data[position / 8] ^= 1 << (position % 8);
+            } else {
+                checksum ^= 1 << (position - data_bits);
+            }
+        }
+
+        Ok((data, checksum))
+    }
+}
+
+fn single_bit_syndromes(params: &CrcParams, data_length: usize) -> Vec<u64> {
+    let linear_params = CrcParams {
+        init: 0,
+        xor_out: 0,
+        ..*params
+    };
+    let mut crc = Crc::new(linear_params);
+    let mut data = vec![0; data_length];
+    let mut result = Vec::with_capacity(data_length * 8 + params.width as usize);
+
+    for position in 0..(data_length * 8) {
+        data[position / 8] = 1 << (position % 8);
+        crc.reset();
+        crc.update(&data[..]);
+        result.push(crc.finish());
+        data[position / 8] = 0;
+    }
+
+    for bit in 0..params.width {
+        result.push(1 << bit);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::crc::{CRC_16_IBM_3740, CRC_32_ISO_HDLC, CRC_8_SMBUS, Crc};
+    use super::*;
+
+    const DATA_LENGTH: usize = 16;
+
+    quickcheck! {
+        fn random_single_bit(data: Vec<u8>, position: usize) -> bool {
+            let data = fixed_length(data);
+            let checksum = Crc::checksum(CRC_32_ISO_HDLC, &data[..]);
+            let corrector = CrcCorrector::new(CRC_32_ISO_HDLC, DATA_LENGTH, 1);
+
+            let (corrupted, corrupted_checksum) = flip(&data[..], checksum, &[position % 160]);
+            corrector.correct(&corrupted[..], corrupted_checksum).unwrap() == (data, checksum)
+        }
+
+        fn random_double_bit(data: Vec<u8>, first: usize, second: usize) -> bool {
+            let data = fixed_length(data);
+            let checksum = Crc::checksum(CRC_32_ISO_HDLC, &data[..]);
+            let corrector = CrcCorrector::new(CRC_32_ISO_HDLC, DATA_LENGTH, 2);
+
+            let positions = [first % 160, second % 160];
+            let (corrupted, corrupted_checksum) = flip(&data[..], checksum, &positions);
+            corrector.correct(&corrupted[..], corrupted_checksum).unwrap() == (data, checksum)
+        }
+
+        fn random_triple_bit(data: Vec<u8>, first: usize, second: usize, third: usize) -> bool {
+            let data = fixed_length(data);
+            let checksum = Crc::checksum(CRC_32_ISO_HDLC, &data[..]);
+            let corrector = CrcCorrector::new(CRC_32_ISO_HDLC, DATA_LENGTH, 2);
+
+            let positions = [first % 160, second % 160, third % 160];
+            if positions[0] == positions[1] || positions[1] == positions[2] ||
+               positions[0] == positions[2] {
+                return true;
+            }
+            let (corrupted, corrupted_checksum) = flip(&data[..], checksum, &positions);
+            corrector.correct(&corrupted[..], corrupted_checksum).is_err()
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let data = b"telemetry frame!";
+        let checksum = Crc::checksum(CRC_16_IBM_3740, data);
+        let corrector = CrcCorrector::new(CRC_16_IBM_3740, data.len(), 1);
+
+        assert!(corrector.locate_errors(data, checksum).unwrap().is_empty());
+
+        let (corrupted, _) = flip(data, checksum, &[32]);
+        assert_eq!(b"teleletry frame!", corrupted.as_slice());
+        assert_eq!(vec![32], corrector.locate_errors(&corrupted[..], checksum).unwrap());
+        assert_eq!((data.to_vec(), checksum),
+                   corrector.correct(&corrupted[..], checksum).unwrap());
+
+        assert_eq!(vec![128 + 15],
+                   corrector.locate_errors(data, checksum ^ 0x8000).unwrap());
+        assert!(corrector.locate_errors(&data[1..], checksum).is_err());
+    }
+
+    #[test]
+    fn ambiguous_syndromes() {
+        // 8-bit syndromes can't tell apart more than 255 positions
+        let data = vec![0x5a; 64];
+        let checksum = Crc::checksum(CRC_8_SMBUS, &data[..]);
+        let corrector = CrcCorrector::new(CRC_8_SMBUS, data.len(), 1);
+
+        let mut corrected = 0;
+        for position in 0..(64 * 8 + 8) {
+            let (corrupted, corrupted_checksum) = flip(&data[..], checksum, &[position]);
+            if let Ok(result) = corrector.correct(&corrupted[..], corrupted_checksum) {
+                assert_eq!((data.clone(), checksum), result);
+                corrected += 1;
+            }
+        }
+        assert!(corrected < 64 * 8);
+    }
+
+    fn fixed_length(data: Vec<u8>) -> Vec<u8> {
+        let mut data = data;
+        data.resize(DATA_LENGTH, 0x42);
+        data
+    }
+
+    fn flip(data: &[u8], checksum: u64, positions: &[usize]) -> (Vec<u8>, u64) {
+        let mut data = data.to_vec();
+        let mut checksum = checksum;
+        for &position in positions {
+            if position < data.len() * 8 {
+                data[position / 8] ^= 1 << (position % 8);
+            } else {
+                checksum ^= 1 << (position - data.len() * 8);
+            }
+        }
+        (data, checksum)
+    }
+}
@@ -0,0 +1,139 @@
+use encoding::bitreader::BitReader;
+use encoding::bitwriter::BitWriter;
+use encoding::checksum::{Adler32, Checksum};
+use encoding::crc::{CRC_32_ISO_HDLC, Crc};
+use encoding::lz77::{Lz77Encoder, MAX_MATCH_LENGTH, Token};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+const MAX_CODE_LENGTH: usize = 15;
+const MAX_CODE_LENGTH_CODE_LENGTH: usize = 7;
+
+const LITERALS: usize = 256;
+const END_OF_BLOCK: usize = 256;
+const LITERAL_LENGTH_CODES: usize = 286;
+const DISTANCE_CODES: usize = 30;
+const CODE_LENGTH_CODES: usize = 19;
+
+const WINDOW_SIZE: usize = 1 << 15;
+const MAX_STORED_LENGTH: usize = 0xffff;
+const BLOCK_TOKENS: usize = 1 << 14;
+
+const BLOCK_STORED: u32 = 0;
+const BLOCK_FIXED: u32 = 1;
+const BLOCK_DYNAMIC: u32 = 2;
+
+static LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43,
+                                 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+
+static LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3,
+                                      3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+
+static DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+                                   257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193,
+                                   12289, 16385, 24577];
+
+static DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8,
+                                        8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+static CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2,
+                                         14, 1, 15];
+
+const ZLIB_METHOD_DEFLATE: u8 = 8;
+const ZLIB_MAX_WINDOW_BITS: u8 = 7;
+const ZLIB_PRESET_DICTIONARY: u8 = 1 << 5;
+const ZLIB_HEADER: [u8; 2] = [0x78, 0x9c];
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_HEADER_CRC: u8 = 1 << 1;
+const GZIP_EXTRA: u8 = 1 << 2;
+const GZIP_NAME: u8 = 1 << 3;
+const GZIP_COMMENT: u8 = 1 << 4;
+const GZIP_OS_UNKNOWN: u8 = 255;
+
+fn fixed_literal_lengths() -> Vec<u8> {
+    let mut lengths = vec![8; 288];
+    for length in &mut lengths[144..256] {
+        *length = 9;
+    }
+    for length in &mut lengths[256..280] {
+        *length = 7;
+    }
+    lengths
+}
+
+fn fixed_distance_lengths() -> Vec<u8> {
+    vec![5; DISTANCE_CODES]
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+struct BitInput<R: Read> {
+    reader: BitReader<R>,
+    bits_read: u64,
+}
+
+impl<R: Read> BitInput<R> {
+    fn new(input: R) -> Self {
+        BitInput {
+            reader: BitReader::new(input),
+            bits_read: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool> {
+        let bit = try!(self.reader.read_bit());
+        self.bits_read += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, length: u8) -> Result<u32> {
+        let data = try!(self.reader.read_bits(length));
+        self.bits_read += length as u64;
+        Ok(data as u32)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        self.read_bits(8).map(|byte| byte as u8)
+    }
+
+    fn read_u16_le(&mut self) -> Result<u16> {
+        let data = try!(self.reader.read_u16_le());
+        self.bits_read += 16;
+        Ok(data)
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32> {
+        let data = try!(self.reader.read_u32_le());
+        self.bits_read += 32;
+        Ok(data)
+    }
+
+    fn read_u32_be(&mut self) -> Result<u32> {
+        let data = try!(self.reader.read_u32_be());
+        self.bits_read += 32;
+        Ok(data)
+    }
+
+    fn align_to_byte(&mut self) -> Result<()> {
+        while self.bits_read % 8 != 0 {
+            let _ = try!(self.read_bit());
+        }
+        Ok(())
+    }
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut checksum = Adler32::new();
+    checksum.update(data);
+    checksum.finish() as u32
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    Crc::checksum(CRC_32_ISO_HDLC, data) as u32
+}
+
+include!("deflate_decoder.rs");
+include!("deflate_encoder.rs");
+include!("deflate_tests.rs");
@@ -0,0 +1,307 @@
+struct DecodingTable {
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    symbols: Vec<u16>,
+}
+
+impl DecodingTable {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - (count as i32);
+            if left < 0 {
+                return Err(invalid_data("Over-subscribed code lengths"));
+            }
+        }
+
+        let mut offsets = [0; MAX_CODE_LENGTH + 2];
+        for length in 1..(MAX_CODE_LENGTH + 1) {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0; offsets[MAX_CODE_LENGTH + 1] as usize];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length > 0 {
+                let offset = &mut offsets[length as usize];
+                symbols[*offset as usize] = symbol as u16;
+                *offset += 1;
+            }
+        }
+
+        let result = DecodingTable {
+            counts: counts,
+            symbols: symbols,
+        };
+        Ok(result)
+    }
+
+    // codes are packed starting from the most significant bit
+    fn decode<R: Read>(&self, input: &mut BitInput<R>) -> Result<usize> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for length in 1..(MAX_CODE_LENGTH + 1) {
+            if try!(input.read_bit()) {
+                code |= 1;
+            }
+
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize] as usize);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(invalid_data("Invalid Huffman code"))
+    }
+}
+
+pub fn inflate<R>(input: R) -> Result<Vec<u8>>
+    where R: Read
+{
+    let mut input = BitInput::new(input);
+    let mut output = vec![];
+    try!(inflate_blocks(&mut input, &mut output));
+    Ok(output)
+}
+
+pub fn zlib_decompress<R>(input: R) -> Result<Vec<u8>>
+    where R: Read
+{
+    let mut input = BitInput::new(input);
+
+    let method = try!(input.read_u8());
+    let flags = try!(input.read_u8());
+    if (method & 0x0f) != ZLIB_METHOD_DEFLATE || (method >> 4) > ZLIB_MAX_WINDOW_BITS {
+        return Err(invalid_data("Unsupported zlib compression method"));
+    }
+    if ((method as u16) << 8 | flags as u16) % 31 != 0 {
+        return Err(invalid_data("Corrupted zlib header"));
+    }
+    if (flags & ZLIB_PRESET_DICTIONARY) > 0 {
+        return Err(invalid_data("zlib preset dictionaries aren't supported"));
+    }
+
+    let mut output = vec![];
+    try!(inflate_blocks(&mut input, &mut output));
+
+    try!(input.align_to_byte());
+    let checksum = try!(input.read_u32_be());
+    if checksum != adler32(&output[..]) {
+        return Err(invalid_data("Adler-32 mismatch"));
+    }
+
+    Ok(output)
+}
+
+pub fn gzip_decompress<R>(input: R) -> Result<Vec<u8>>
+    where R: Read
+{
+    let mut input = BitInput::new(input);
+    let mut output = vec![];
+    let mut members = 0;
+
+    loop {
+        let first_byte = match input.read_u8() {
+            Ok(byte) => byte,
+            Err(_) if members > 0 => break,
+            Err(e) => return Err(e),
+        };
+
+        try!(read_gzip_header(&mut input, first_byte));
+
+        let member_start = output.len();
+        try!(inflate_blocks(&mut input, &mut output));
+
+        try!(input.align_to_byte());
+        let checksum = try!(input.read_u32_le());
+        let length = try!(input.read_u32_le());
+
+        let member = &output[member_start..];
+        if checksum != crc32(member) {
+            return Err(invalid_data("CRC-32 mismatch"));
+        }
+        if length != member.len() as u32 {
+            return Err(invalid_data("gzip length mismatch"));
+        }
+
+        members += 1;
+    }
+
+    Ok(output)
+}
+
+fn read_gzip_header<R: Read>(input: &mut BitInput<R>, first_byte: u8) -> Result<()> {
+    let second_byte = try!(input.read_u8());
+    if [first_byte, second_byte] != GZIP_MAGIC {
+        return Err(invalid_data("Not a gzip stream"));
+    }
+
+    if try!(input.read_u8()) != ZLIB_METHOD_DEFLATE {
+        return Err(invalid_data("Unsupported gzip compression method"));
+    }
+
+    let flags = try!(input.read_u8());
+    let _modification_time = try!(input.read_u32_le());
+    let _extra_flags = try!(input.read_u8());
+    let _os = try!(input.read_u8());
+
+    if (flags & GZIP_EXTRA) > 0 {
+        let length = try!(input.read_u16_le());
+        for _ in 0..length {
+            let _ = try!(input.read_u8());
+        }
+    }
+
+    for &flag in &[GZIP_NAME, GZIP_COMMENT] {
+        if (flags & flag) > 0 {
+            while try!(input.read_u8()) != 0 {}
+        }
+    }
+
+    if (flags & GZIP_HEADER_CRC) > 0 {
+        let _ = try!(input.read_u16_le());
+    }
+
+    Ok(())
+}
+
+fn inflate_blocks<R: Read>(input: &mut BitInput<R>, output: &mut Vec<u8>) -> Result<()> {
+    loop {
+        let last_block = try!(input.read_bit());
+
+        match try!(input.read_bits(2)) {
+            BLOCK_STORED => try!(inflate_stored(input, output)),
+            BLOCK_FIXED => {
+                let literals = try!(DecodingTable::new(&fixed_literal_lengths()[..]));
+                let distances = try!(DecodingTable::new(&fixed_distance_lengths()[..]));
+                try!(inflate_codes(input, output, &literals, &distances));
+            }
+            BLOCK_DYNAMIC => {
+                let (literals, distances) = try!(read_dynamic_tables(input));
+                try!(inflate_codes(input, output, &literals, &distances));
+            }
+            _ => return Err(invalid_data("Invalid block type")),
+        }
+
+        if last_block {
+            return Ok(());
+        }
+    }
+}
+
+fn inflate_stored<R: Read>(input: &mut BitInput<R>, output: &mut Vec<u8>) -> Result<()> {
+    try!(input.align_to_byte());
+
+    let length = try!(input.read_u16_le());
+    let length_complement = try!(input.read_u16_le());
+    if length != !length_complement {
+        return Err(invalid_data("Stored block length mismatch"));
+    }
+
+    for _ in 0..length {
+        output.push(try!(input.read_u8()));
+    }
+
+    Ok(())
+}
+
+fn inflate_codes<R: Read>(input: &mut BitInput<R>,
+                          output: &mut Vec<u8>,
+                          literals: &DecodingTable,
+                          distances: &DecodingTable)
+                          -> Result<()> {
+    loop {
+        let symbol = try!(literals.decode(input));
+
+        if symbol < LITERALS {
+            output.push(symbol as u8);
+        } else if symbol == END_OF_BLOCK {
+            return Ok(());
+        } else {
+            let index = symbol - END_OF_BLOCK - 1;
+            if index >= LENGTH_BASE.len() {
+                return Err(invalid_data("Invalid length code"));
+            }
+            let extra = try!(input.read_bits(LENGTH_EXTRA_BITS[index]));
+            let length = LENGTH_BASE[index] as usize + extra as usize;
+
+            let index = try!(distances.decode(input));
+            if index >= DISTANCE_BASE.len() {
+                return Err(invalid_data("Invalid distance code"));
+            }
+            let extra = try!(input.read_bits(DISTANCE_EXTRA_BITS[index]));
+            let distance = DISTANCE_BASE[index] as usize + extra as usize;
+
+            if distance > output.len() {
+                return Err(invalid_data("Distance is too far back"));
+            }
+
+            let start = output.len() - distance;
+            for i in start..(start + length) {
+                let byte = output[i];
+                output.push(byte);
+            }
+        }
+    }
+}
+
+fn read_dynamic_tables<R: Read>(input: &mut BitInput<R>)
+                                -> Result<(DecodingTable, DecodingTable)> {
+    let literals_count = try!(input.read_bits(5)) as usize + 257;
+    let distances_count = try!(input.read_bits(5)) as usize + 1;
+    let code_lengths_count = try!(input.read_bits(4)) as usize + 4;
+
+    if literals_count > LITERAL_LENGTH_CODES || distances_count > DISTANCE_CODES {
+        return Err(invalid_data("Too many length or distance codes"));
+    }
+
+    let mut code_length_lengths = [0; CODE_LENGTH_CODES];
+    for &symbol in &CODE_LENGTH_ORDER[..code_lengths_count] {
+        code_length_lengths[symbol] = try!(input.read_bits(3)) as u8;
+    }
+    let code_lengths = try!(DecodingTable::new(&code_length_lengths[..]));
+
+    let total = literals_count + distances_count;
+    let mut lengths = Vec::with_capacity(total);
+
+    while lengths.len() < total {
+        let symbol = try!(code_lengths.decode(input));
+        let (length, repeat) = match symbol {
+            0...15 => (symbol as u8, 1),
+            16 => {
+                let previous = match lengths.last() {
+                    Some(&length) => length,
+                    None => return Err(invalid_data("Repeat without a previous length")),
+                };
+                (previous, 3 + try!(input.read_bits(2)) as usize)
+            }
+            17 => (0, 3 + try!(input.read_bits(3)) as usize),
+            _ => (0, 11 + try!(input.read_bits(7)) as usize),
+        };
+
+        if lengths.len() + repeat > total {
+            return Err(invalid_data("Too many code lengths"));
+        }
+        for _ in 0..repeat {
+            lengths.push(length);
+        }
+    }
+
+    if lengths[END_OF_BLOCK] == 0 {
+        return Err(invalid_data("Missing end-of-block code"));
+    }
+
+    let literals = try!(DecodingTable::new(&lengths[..literals_count]));
+    let distances = try!(DecodingTable::new(&lengths[literals_count..]));
+    Ok((literals, distances))
+}
@@ -0,0 +1,441 @@
+struct BlockStatistics {
+    literals: Vec<u64>,
+    distances: Vec<u64>,
+}
+
+impl BlockStatistics {
+    fn new(tokens: &[Token]) -> Self {
+        let mut literals = vec![0; LITERAL_LENGTH_CODES];
+        let mut distances = vec![0; DISTANCE_CODES];
+
+        for token in tokens {
+            match *token {
+                Token::Literal(byte) => literals[byte as usize] += 1,
+                Token::Match { distance, length } => {
+                    literals[END_OF_BLOCK + 1 + length_index(length)] += 1;
+                    distances[distance_index(distance)] += 1;
+                }
+            }
+        }
+        literals[END_OF_BLOCK] = 1;
+
+        BlockStatistics {
+            literals: literals,
+            distances: distances,
+        }
+    }
+
+    fn data_bits(&self, literal_lengths: &[u8], distance_lengths: &[u8]) -> u64 {
+        let mut result = 0;
+
+        for (symbol, &frequency) in self.literals.iter().enumerate() {
+            result += frequency * literal_lengths[symbol] as u64;
+            if symbol > END_OF_BLOCK {
+                result += frequency * LENGTH_EXTRA_BITS[symbol - END_OF_BLOCK - 1] as u64;
+            }
+        }
+
+        for (symbol, &frequency) in self.distances.iter().enumerate() {
+            result += frequency * (distance_lengths[symbol] + DISTANCE_EXTRA_BITS[symbol]) as u64;
+        }
+
+        result
+    }
+}
+
+struct DynamicHeader {
+    literal_lengths: Vec<u8>,
+    distance_lengths: Vec<u8>,
+    literals_count: usize,
+    distances_count: usize,
+    code_length_lengths: Vec<u8>,
+    code_lengths: Vec<(usize, u32)>,
+    code_length_codes_count: usize,
+}
+
+impl DynamicHeader {
+    fn new(statistics: &BlockStatistics) -> Self {
+        let literal_lengths = huffman_lengths(&statistics.literals[..], MAX_CODE_LENGTH);
+        let distance_lengths = huffman_lengths(&statistics.distances[..], MAX_CODE_LENGTH);
+
+        let literals_count = used_length(&literal_lengths[..], 257);
+        let distances_count = used_length(&distance_lengths[..], 1);
+
+        let mut all_lengths = literal_lengths[..literals_count].to_vec();
+        all_lengths.extend_from_slice(&distance_lengths[..distances_count]);
+        let code_lengths = run_length_encode(&all_lengths[..]);
+
+        let mut frequencies = [0; CODE_LENGTH_CODES];
+        for &(symbol, _) in &code_lengths {
+            frequencies[symbol] += 1;
+        }
+        let code_length_lengths = huffman_lengths(&frequencies[..], MAX_CODE_LENGTH_CODE_LENGTH);
+
+        let ordered_lengths = CODE_LENGTH_ORDER.iter()
+            .map(|&symbol| code_length_lengths[symbol])
+            .collect::<Vec<u8>>();
+        let code_length_codes_count = used_length(&ordered_lengths[..], 4);
+
+        DynamicHeader {
+            literal_lengths: literal_lengths,
+            distance_lengths: distance_lengths,
+            literals_count: literals_count,
+            distances_count: distances_count,
+            code_length_lengths: code_length_lengths,
+            code_lengths: code_lengths,
+            code_length_codes_count: code_length_codes_count,
+        }
+    }
+
+    fn header_bits(&self) -> u64 {
+        let mut result = 5 + 5 + 4 + 3 * self.code_length_codes_count as u64;
+        for &(symbol, _) in &self.code_lengths {
+            result += (self.code_length_lengths[symbol] + code_length_extra_bits(symbol)) as u64;
+        }
+        result
+    }
+
+    fn write<W: Write>(&self, output: &mut BitWriter<W>) -> Result<()> {
+        try!(write_bits(output, (self.literals_count - 257) as u32, 5));
+        try!(write_bits(output, (self.distances_count - 1) as u32, 5));
+        try!(write_bits(output, (self.code_length_codes_count - 4) as u32, 4));
+
+        for &symbol in &CODE_LENGTH_ORDER[..self.code_length_codes_count] {
+            try!(write_bits(output, self.code_length_lengths[symbol] as u32, 3));
+        }
+
+        let codes = canonical_codes(&self.code_length_lengths[..]);
+        for &(symbol, extra) in &self.code_lengths {
+            try!(write_code(output, codes[symbol], self.code_length_lengths[symbol]));
+            try!(write_bits(output, extra, code_length_extra_bits(symbol)));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn deflate(input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = vec![];
+    try!(deflate_to(input, &mut output));
+    Ok(output)
+}
+
+pub fn zlib_compress(input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = ZLIB_HEADER.to_vec();
+    try!(deflate_to(input, &mut output));
+
+    let checksum = adler32(input);
+    for shift in &[24, 16, 8, 0] {
+        output.push((checksum >> shift) as u8);
+    }
+
+    Ok(output)
+}
+
+pub fn gzip_compress(input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = GZIP_MAGIC.to_vec();
+    output.extend_from_slice(&[ZLIB_METHOD_DEFLATE, 0, 0, 0, 0, 0, 0, GZIP_OS_UNKNOWN]);
+    try!(deflate_to(input, &mut output));
+
+    for &value in &[crc32(input), input.len() as u32] {
+        for shift in &[0, 8, 16, 24] {
+            output.push((value >> shift) as u8);
+        }
+    }
+
+    Ok(output)
+}
+
+fn deflate_to<W: Write>(input: &[u8], output: W) -> Result<()> {
+    let mut output = BitWriter::new(output);
+    let tokens = Lz77Encoder::new(WINDOW_SIZE, MAX_MATCH_LENGTH).encode(input);
+
+    if tokens.is_empty() {
+        try!(write_fixed_block(&mut output, &[], true));
+    }
+
+    let blocks_count = (tokens.len() + BLOCK_TOKENS - 1) / BLOCK_TOKENS;
+    let mut position = 0;
+
+    for (i, block) in tokens.chunks(BLOCK_TOKENS).enumerate() {
+        let last_block = i + 1 == blocks_count;
+        let block_length = block.iter().map(token_length).sum::<usize>();
+        let block_input = &input[position..(position + block_length)];
+        position += block_length;
+
+        let statistics = BlockStatistics::new(block);
+        let header = DynamicHeader::new(&statistics);
+
+        let stored_bits = stored_bits(block_input.len());
+        let fixed_bits = statistics.data_bits(&fixed_literal_lengths()[..],
+                                              &fixed_distance_lengths()[..]);
+        let dynamic_bits = header.header_bits() +
+                           statistics.data_bits(&header.literal_lengths[..],
+                                                &header.distance_lengths[..]);
+
+        if stored_bits <= fixed_bits && stored_bits <= dynamic_bits {
+            try!(write_stored_blocks(&mut output, block_input, last_block));
+        } else if fixed_bits <= dynamic_bits {
+            try!(write_fixed_block(&mut output, block, last_block));
+        } else {
+            try!(write_dynamic_block(&mut output, block, &header, last_block));
+        }
+    }
+
+    try!(output.align_to_byte());
+    output.flush()
+}
+
+fn write_stored_blocks<W: Write>(output: &mut BitWriter<W>,
+                                 input: &[u8],
+                                 last_block: bool)
+                                 -> Result<()> {
+    let chunks_count = (input.len() + MAX_STORED_LENGTH - 1) / MAX_STORED_LENGTH;
+
+    for (i, chunk) in input.chunks(MAX_STORED_LENGTH).enumerate() {
+        try!(output.write_bit(last_block && i + 1 == chunks_count));
+        try!(write_bits(output, BLOCK_STORED, 2));
+        try!(output.align_to_byte());
+
+        let length = chunk.len() as u16;
+        try!(output.write_u16_le(length));
+        try!(output.write_u16_le(!length));
+        try!(output.write_bytes(chunk));
+    }
+
+    Ok(())
+}
+
+fn write_fixed_block<W: Write>(output: &mut BitWriter<W>,
+                               tokens: &[Token],
+                               last_block: bool)
+                               -> Result<()> {
+    try!(output.write_bit(last_block));
+    try!(write_bits(output, BLOCK_FIXED, 2));
+    write_tokens(output,
+                 tokens,
+                 &fixed_literal_lengths()[..],
+                 &fixed_distance_lengths()[..])
+}
+
+fn write_dynamic_block<W: Write>(output: &mut BitWriter<W>,
+                                 tokens: &[Token],
+                                 header: &DynamicHeader,
+                                 last_block: bool)
+                                 -> Result<()> {
+    try!(output.write_bit(last_block));
+    try!(write_bits(output, BLOCK_DYNAMIC, 2));
+    try!(header.write(output));
+    write_tokens(output,
+                 tokens,
+                 &header.literal_lengths[..],
+                 &header.distance_lengths[..])
+}
+
+fn write_tokens<W: Write>(output: &mut BitWriter<W>,
+                          tokens: &[Token],
+                          literal_lengths: &[u8],
+                          distance_lengths: &[u8])
+                          -> Result<()> {
+    let literal_codes = canonical_codes(literal_lengths);
+    let distance_codes = canonical_codes(distance_lengths);
+
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => {
+                let symbol = byte as usize;
+                try!(write_code(output, literal_codes[symbol], literal_lengths[symbol]));
+            }
+            Token::Match { distance, length } => {
+                let index = length_index(length);
+                let symbol = END_OF_BLOCK + 1 + index;
+                try!(write_code(output, literal_codes[symbol], literal_lengths[symbol]));
+                try!(write_bits(output,
+                                (length - LENGTH_BASE[index] as usize) as u32,
+                                LENGTH_EXTRA_BITS[index]));
+
+                let index = distance_index(distance);
+                try!(write_code(output, distance_codes[index], distance_lengths[index]));
+                try!(write_bits(output,
+                                (distance - DISTANCE_BASE[index] as usize) as u32,
+                                DISTANCE_EXTRA_BITS[index]));
+            }
+        }
+    }
+
+    write_code(output,
+               literal_codes[END_OF_BLOCK],
+               literal_lengths[END_OF_BLOCK])
+}
+
+fn write_bits<W: Write>(output: &mut BitWriter<W>, data: u32, length: u8) -> Result<()> {
+    output.write_bits(data as u64, length)
+}
+
+// Huffman codes are packed starting from the most significant bit
+fn write_code<W: Write>(output: &mut BitWriter<W>, code: u32, length: u8) -> Result<()> {
+    for i in (0..length).rev() {
+        try!(output.write_bit((code >> i) & 1 > 0));
+    }
+    Ok(())
+}
+
+fn stored_bits(length: usize) -> u64 {
+    let chunks_count = (length + MAX_STORED_LENGTH - 1) / MAX_STORED_LENGTH;
+    chunks_count as u64 * (3 + 7 + 32) + length as u64 * 8
+}
+
+fn token_length(token: &Token) -> usize {
+    match *token {
+        Token::Literal(_) => 1,
+        Token::Match { length, .. } => length,
+    }
+}
+
+fn length_index(length: usize) -> usize {
+    LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap()
+}
+
+fn distance_index(distance: usize) -> usize {
+    DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap()
+}
+
+fn code_length_extra_bits(symbol: usize) -> u8 {
+    match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
+
+fn used_length(lengths: &[u8], minimum: usize) -> usize {
+    let used = lengths.iter().rposition(|&length| length > 0).map_or(0, |i| i + 1);
+    if used < minimum { minimum } else { used }
+}
+
+// zero lengths are folded into codes 17 and 18, repeated lengths into code 16
+fn run_length_encode(lengths: &[u8]) -> Vec<(usize, u32)> {
+    let mut result = vec![];
+    let mut i = 0;
+
+    while i < lengths.len() {
+        let length = lengths[i];
+        let mut run = lengths[i..].iter().take_while(|&&l| l == length).count();
+        i += run;
+
+        if length == 0 {
+            while run >= 11 {
+                let repeat = if run > 138 { 138 } else { run };
+                result.push((18, (repeat - 11) as u32));
+                run -= repeat;
+            }
+            if run >= 3 {
+                result.push((17, (run - 3) as u32));
+                run = 0;
+            }
+        } else {
+            result.push((length as usize, 0));
+            run -= 1;
+            while run >= 3 {
+                let repeat = if run > 6 { 6 } else { run };
+                result.push((16, (repeat - 3) as u32));
+                run -= repeat;
+            }
+        }
+
+        for _ in 0..run {
+            result.push((length as usize, 0));
+        }
+    }
+
+    result
+}
+
+// frequencies are halved until the tree fits into the length limit,
+// at least two symbols are always present so that the code is complete
+fn huffman_lengths(frequencies: &[u64], max_length: usize) -> Vec<u8> {
+    let mut frequencies = frequencies.to_vec();
+    let mut padding = 0;
+    while frequencies.iter().filter(|&&frequency| frequency > 0).count() < 2 {
+        if frequencies[padding] == 0 {
+            frequencies[padding] = 1;
+        }
+        padding += 1;
+    }
+
+    loop {
+        let lengths = unlimited_huffman_lengths(&frequencies[..]);
+        if lengths.iter().all(|&length| length as usize <= max_length) {
+            return lengths;
+        }
+
+        for frequency in &mut frequencies {
+            if *frequency > 0 {
+                *frequency = (*frequency + 1) / 2;
+            }
+        }
+    }
+}
+
+fn unlimited_huffman_lengths(frequencies: &[u64]) -> Vec<u8> {
+    let mut weights = vec![];
+    let mut parents = vec![];
+    let mut active = vec![];
+
+    for &frequency in frequencies {
+        if frequency > 0 {
+            active.push(weights.len());
+        }
+        weights.push(frequency);
+        parents.push(None);
+    }
+
+    while active.len() > 1 {
+        active.sort_by(|&a, &b| (weights[b], b).cmp(&(weights[a], a)));
+        let first = active.pop().unwrap();
+        let second = active.pop().unwrap();
+
+        let node = weights.len();
+        weights.push(weights[first] + weights[second]);
+        parents.push(None);
+        parents[first] = Some(node);
+        parents[second] = Some(node);
+        active.push(node);
+    }
+
+    (0..frequencies.len())
+        .map(|symbol| {
+            let mut length = 0;
+            let mut node = symbol;
+            while let Some(parent) = parents[node] {
+                length += 1;
+                node = parent;
+            }
+            length
+        })
+        .collect()
+}
+
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut counts = [0u32; MAX_CODE_LENGTH + 1];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+    counts[0] = 0;
+
+    let mut next_code = [0u32; MAX_CODE_LENGTH + 1];
+    let mut code = 0;
+    for length in 1..(MAX_CODE_LENGTH + 1) {
+        code = (code + counts[length - 1]) << 1;
+        next_code[length] = code;
+    }
+
+    lengths.iter()
+        .map(|&length| {
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+            code
+        })
+        .collect()
+}
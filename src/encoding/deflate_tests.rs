@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "Hello, Rust! Hello, Rust!\n";
+
+    static ZLIB_TEXT: [u8; 25] = [120, 156, 243, 72, 205, 201, 201, 215, 81, 8, 42, 45, 46, 81,
+                                  84, 240, 64, 226, 112, 1, 0, 116, 179, 8, 73];
+
+    static ZLIB_STORED_TEXT: [u8; 37] = [120, 1, 1, 26, 0, 229, 255, 72, 101, 108, 108, 111, 44,
+                                         32, 82, 117, 115, 116, 33, 32, 72, 101, 108, 108, 111,
+                                         44, 32, 82, 117, 115, 116, 33, 10, 116, 179, 8, 73];
+
+    static GZIP_TEXT: [u8; 37] = [31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 243, 72, 205, 201, 201, 215,
+                                  81, 8, 42, 45, 46, 81, 84, 240, 64, 226, 112, 1, 0, 192, 228,
+                                  66, 254, 26, 0, 0, 0];
+
+    static DEFLATE_FIXED: [u8; 16] = [203, 205, 44, 46, 6, 161, 130, 130, 76, 133, 162, 204, 178,
+                                      212, 34, 0];
+
+    static ZLIB_DYNAMIC: [u8; 162] =
+        [120, 218, 125, 210, 59, 14, 194, 48, 20, 68, 209, 62, 171, 240, 18, 60, 239, 249, 203,
+         110, 76, 160, 64, 138, 40, 168, 88, 62, 138, 112, 67, 50, 67, 101, 233, 78, 115, 100,
+         123, 123, 60, 239, 33, 94, 194, 184, 190, 198, 58, 110, 251, 17, 150, 109, 143, 248,
+         141, 239, 111, 181, 67, 157, 217, 143, 121, 246, 116, 234, 115, 200, 231, 97, 46, 133,
+         44, 115, 170, 204, 217, 168, 179, 115, 39, 162, 128, 2, 74, 10, 147, 84, 184, 182, 34,
+         209, 75, 205, 84, 139, 34, 184, 85, 113, 155, 228, 118, 201, 181, 168, 185, 6, 198, 53,
+         227, 159, 192, 57, 215, 146, 224, 90, 86, 92, 43, 154, 91, 255, 112, 27, 229, 118, 202,
+         245, 40, 62, 45, 4, 215, 77, 113, 221, 37, 215, 147, 230, 122, 102, 92, 47, 156, 91, 5,
+         183, 41, 46, 121, 242, 229, 3, 73, 60, 77, 8];
+
+    quickcheck! {
+        fn random_deflate(input: Vec<u8>) -> bool {
+            let compressed = deflate(&input[..]).unwrap();
+            inflate(&compressed[..]).unwrap() == input
+        }
+
+        fn random_zlib(input: Vec<u8>) -> bool {
+            let compressed = zlib_compress(&input[..]).unwrap();
+            zlib_decompress(&compressed[..]).unwrap() == input
+        }
+
+        fn random_gzip(input: Vec<u8>) -> bool {
+            let compressed = gzip_compress(&input[..]).unwrap();
+            gzip_decompress(&compressed[..]).unwrap() == input
+        }
+
+        fn random_repetitive(input: Vec<u8>, repeats: u8) -> bool {
+            let mut data = vec![];
+            for _ in 0..(repeats % 16) {
+                data.extend_from_slice(&input[..]);
+            }
+            let compressed = zlib_compress(&data[..]).unwrap();
+            zlib_decompress(&compressed[..]).unwrap() == data
+        }
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(1, adler32(b""));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+        assert_eq!(0, crc32(b""));
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn known_vectors() {
+        let input_slice = INPUT_TEXT.as_bytes();
+        assert_eq!(input_slice, zlib_decompress(&ZLIB_TEXT[..]).unwrap().as_slice());
+        assert_eq!(input_slice,
+                   zlib_decompress(&ZLIB_STORED_TEXT[..]).unwrap().as_slice());
+        assert_eq!(input_slice, gzip_decompress(&GZIP_TEXT[..]).unwrap().as_slice());
+        assert_eq!(b"mississippi river",
+                   inflate(&DEFLATE_FIXED[..]).unwrap().as_slice());
+        assert_eq!(dynamic_text().as_bytes(),
+                   zlib_decompress(&ZLIB_DYNAMIC[..]).unwrap().as_slice());
+    }
+
+    #[test]
+    fn block_types() {
+        let stored = (0..200).map(|i| (i * 37 % 251) as u8).collect::<Vec<u8>>();
+        let compressed = deflate(&stored[..]).unwrap();
+        assert_eq!(BLOCK_STORED as u8, (compressed[0] >> 1) & 3);
+        assert_eq!(stored, inflate(&compressed[..]).unwrap());
+
+        let compressed = deflate(b"mississippi river").unwrap();
+        assert_eq!(&DEFLATE_FIXED[..], compressed.as_slice());
+
+        let text = dynamic_text();
+        let compressed = zlib_compress(text.as_bytes()).unwrap();
+        assert_eq!(BLOCK_DYNAMIC as u8, (compressed[2] >> 1) & 3);
+        assert!(compressed.len() < ZLIB_DYNAMIC.len() * 3 / 2);
+        assert_eq!(text.as_bytes(), zlib_decompress(&compressed[..]).unwrap().as_slice());
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(vec![3, 0], deflate(b"").unwrap());
+        assert!(inflate(&[3, 0][..]).unwrap().is_empty());
+        assert!(gzip_decompress(&gzip_compress(b"").unwrap()[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn large_input() {
+        let input = (0..300_000).map(|i| (i % 253 * i % 253) as u8).collect::<Vec<u8>>();
+        let compressed = zlib_compress(&input[..]).unwrap();
+        assert_eq!(input, zlib_decompress(&compressed[..]).unwrap());
+
+        let input = (0..200_000)
+            .map(|i| (i * 7919 % 256) as u8 ^ (i >> 9) as u8)
+            .collect::<Vec<u8>>();
+        let compressed = gzip_compress(&input[..]).unwrap();
+        assert_eq!(input, gzip_decompress(&compressed[..]).unwrap());
+    }
+
+    #[test]
+    fn multiple_gzip_members() {
+        let mut data = gzip_compress(b"first ").unwrap();
+        data.extend_from_slice(&gzip_compress(b"second").unwrap());
+        assert_eq!(b"first second", gzip_decompress(&data[..]).unwrap().as_slice());
+    }
+
+    #[test]
+    fn gzip_header_fields() {
+        let mut data = GZIP_MAGIC.to_vec();
+        data.extend_from_slice(&[ZLIB_METHOD_DEFLATE,
+                                 GZIP_EXTRA | GZIP_NAME | GZIP_COMMENT | GZIP_HEADER_CRC]);
+        data.extend_from_slice(&[0, 0, 0, 0, 0, GZIP_OS_UNKNOWN]);
+        data.extend_from_slice(&[2, 0, 42, 42]);
+        data.extend_from_slice(b"name.txt\0comment\0");
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&GZIP_TEXT[10..]);
+        assert_eq!(INPUT_TEXT.as_bytes(), gzip_decompress(&data[..]).unwrap().as_slice());
+    }
+
+    #[test]
+    fn corrupted_data() {
+        let mut data = ZLIB_TEXT.to_vec();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(zlib_decompress(&data[..]).is_err());
+
+        let mut data = GZIP_TEXT.to_vec();
+        data[30] ^= 1;
+        assert!(gzip_decompress(&data[..]).is_err());
+
+        let mut data = ZLIB_TEXT.to_vec();
+        data[1] ^= 1;
+        assert!(zlib_decompress(&data[..]).is_err());
+
+        let mut data = ZLIB_STORED_TEXT.to_vec();
+        data[5] ^= 1;
+        assert!(zlib_decompress(&data[..]).is_err());
+
+        assert!(inflate(&[7][..]).is_err());
+        assert!(gzip_decompress(&[][..]).is_err());
+        assert!(gzip_decompress(&ZLIB_TEXT[..]).is_err());
+        assert!(zlib_decompress(&ZLIB_TEXT[..10]).is_err());
+    }
+
+    #[test]
+    fn canonical_code_lengths() {
+        let lengths = huffman_lengths(&[5, 9, 12, 13, 16, 45], MAX_CODE_LENGTH);
+        assert_eq!(vec![4, 4, 3, 3, 3, 1], lengths);
+        assert_eq!(vec![14, 15, 4, 5, 6, 0], canonical_codes(&lengths[..]));
+
+        let frequencies = (0..40).map(|i| 1 << i).collect::<Vec<u64>>();
+        let lengths = huffman_lengths(&frequencies[..], MAX_CODE_LENGTH_CODE_LENGTH);
+        assert!(lengths.iter().all(|&length| length > 0 && length <= 7));
+        assert!(DecodingTable::new(&lengths[..]).is_ok());
+
+        assert_eq!(vec![1, 1, 0], huffman_lengths(&[0, 0, 0], MAX_CODE_LENGTH));
+        assert_eq!(vec![1, 0, 1], huffman_lengths(&[0, 0, 3], MAX_CODE_LENGTH));
+    }
+
+    fn dynamic_text() -> String {
+        let mut text = String::new();
+        for i in 0..40 {
+            let padding = (0..(i % 7)).map(|_| "x").collect::<String>();
+            text.push_str(&format!("line {}: abracadabra {}\n", i, padding));
+        }
+        text
+    }
+}
@@ -0,0 +1,98 @@
+use encoding::binary_to_text::INVALID_CHAR;
+use std::io::{Error, ErrorKind, Result};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hex {
+    pub uppercase: bool,
+}
+
+pub const HEX: Hex = Hex { uppercase: false };
+pub const HEX_UPPER: Hex = Hex { uppercase: true };
+
+impl Hex {
+    pub fn encode(&self, data: &[u8]) -> String {
+        let table = if self.uppercase {
+            UPPER_ENCODE_TABLE
+        } else {
+            LOWER_ENCODE_TABLE
+        };
+        let mut result = Vec::with_capacity(data.len() * 2);
+        for &byte in data {
+            result.push(table[(byte >> 4) as usize]);
+            result.push(table[(byte & 0xf) as usize]);
+        }
+        String::from_utf8(result).unwrap()
+    }
+
+    // either case is accepted, whitespace between the digits of hex dumps is skipped
+    pub fn decode(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let mut result = Vec::with_capacity(input.len() / 2);
+        let mut high = None;
+        for (position, &ch) in input.iter().enumerate() {
+            let value = DECODE_TABLE[ch as usize];
+            if ch.is_ascii_whitespace() {
+                continue;
+            } else if value == INVALID_CHAR {
+                let message = format!("Invalid hex character 0x{:02x} at {}", ch, position);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+
+            match high.take() {
+                Some(high) => result.push((high << 4) | value),
+                None => high = Some(value),
+            }
+        }
+
+        if high.is_some() {
+            return Err(Error::new(ErrorKind::InvalidData, "Odd number of hex digits"));
+        }
+        Ok(result)
+    }
+}
+
+static LOWER_ENCODE_TABLE: &'static [u8] = b"0123456789abcdef";
+static UPPER_ENCODE_TABLE: &'static [u8] = b"0123456789ABCDEF";
+
+// accepts both cases
+static DECODE_TABLE: [u8; 256] =
+    [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 255, 255, 255,
+     255, 255, 255, 255, 10, 11, 12, 13, 14, 15, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 10, 11, 12,
+     13, 14, 15, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
+     255, 255, 255, 255];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn random_roundtrip(data: Vec<u8>) -> bool {
+            HEX.decode(HEX.encode(&data[..]).as_bytes()).unwrap() == data &&
+            HEX.decode(HEX_UPPER.encode(&data[..]).as_bytes()).unwrap() == data
+        }
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!("", HEX.encode(b""));
+        assert_eq!("666f6f0aff", HEX.encode(b"foo\n\xff"));
+        assert_eq!("666F6F0AFF", HEX_UPPER.encode(b"foo\n\xff"));
+        assert_eq!(b"foo\n\xff".to_vec(), HEX.decode(b"66 6f 6F\n0a Ff").unwrap());
+    }
+
+    #[test]
+    fn invalid() {
+        let error = HEX.decode(b"66 6g").unwrap_err();
+        assert_eq!("Invalid hex character 0x67 at 4", error.to_string());
+        assert!(HEX.decode(b"666").is_err());
+    }
+}
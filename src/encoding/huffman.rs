@@ -1,8 +1,10 @@
 use encoding::bitreader::BitReader;
 use encoding::bitwriter::BitWriter;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
 use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
 use std::mem;
+use std::str;
 use structs::binary_tree::BinaryTree;
 use structs::bitset::BitSet;
 
@@ -11,7 +13,25 @@ type CharSlice<'a> = &'a [u8];
 type DictLength = u16;
 
 type CodeLength = u8;
-type CodeData = u16;
+type CodeData = u32;
+
+const MAX_SYMBOL_LENGTH: usize = 255;
+const MAX_WORDS: usize = 4096;
+const MAX_SYMBOLS: usize = 16384;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Symbols {
+    Bytes(usize),
+    CodePoints,
+    Words,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum CharClass {
+    Word,
+    Space,
+    Other,
+}
 
 #[derive(PartialEq, Debug)]
 enum State {
@@ -28,7 +48,7 @@ struct NodeData {
 
 type Tree = BinaryTree<NodeData>;
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 struct Code {
     length: CodeLength,
     data: CodeData,
@@ -57,6 +77,129 @@ fn read_char<R>(input: &mut BitReader<R>, max_char_length: usize) -> Option<Char
     }
 }
 
+fn split_code_points(ch: CharSlice) -> Vec<Char> {
+    match str::from_utf8(ch) {
+        Ok(text) => text.chars().map(|c| c.to_string().into_bytes()).collect(),
+        Err(_) => ch.iter().map(|&byte| vec![byte]).collect(),
+    }
+}
+
+fn char_class(ch: CharSlice) -> CharClass {
+    let code_point = str::from_utf8(ch).ok().and_then(|text| text.chars().next());
+    match code_point {
+        Some(c) if c.is_alphanumeric() || c == '_' => CharClass::Word,
+        Some(c) if c.is_whitespace() => CharClass::Space,
+        _ => CharClass::Other,
+    }
+}
+
+fn utf8_sequence_length(lead: u8) -> usize {
+    if lead >> 7 == 0 {
+        1
+    } else if lead >> 5 == 0b110 {
+        2
+    } else if lead >> 4 == 0b1110 {
+        3
+    } else if lead >> 3 == 0b11110 {
+        4
+    } else {
+        1
+    }
+}
+
+struct Tokenizer {
+    symbols: Symbols,
+    pending_byte: Option<u8>,
+    pending_char: Option<Char>,
+}
+
+impl Tokenizer {
+    fn new(symbols: Symbols) -> Self {
+        Tokenizer {
+            symbols: symbols,
+            pending_byte: None,
+            pending_char: None,
+        }
+    }
+
+    fn next_char<R>(&mut self, input: &mut BitReader<R>) -> Option<Char>
+        where R: Read
+    {
+        match self.symbols {
+            Symbols::Bytes(max_char_length) => read_char(input, max_char_length),
+            Symbols::CodePoints => self.next_code_point(input),
+            Symbols::Words => self.next_word(input),
+        }
+    }
+
+    fn next_word<R>(&mut self, input: &mut BitReader<R>) -> Option<Char>
+        where R: Read
+    {
+        let first = self.pending_char.take().or_else(|| self.next_code_point(input));
+        let mut result = match first {
+            Some(ch) => ch,
+            None => return None,
+        };
+
+        let class = char_class(&result);
+        if class == CharClass::Other {
+            return Some(result);
+        }
+
+        while let Some(ch) = self.next_code_point(input) {
+            let same_word = char_class(&ch) == class &&
+                            result.len() + ch.len() <= MAX_SYMBOL_LENGTH;
+            if same_word {
+                result.extend(ch);
+            } else {
+                self.pending_char = Some(ch);
+                break;
+            }
+        }
+
+        Some(result)
+    }
+
+    // broken sequences are kept as they are, the byte which broke one
+    // starts the next code point
+    fn next_code_point<R>(&mut self, input: &mut BitReader<R>) -> Option<Char>
+        where R: Read
+    {
+        let lead = match self.next_byte(input) {
+            Some(byte) => byte,
+            None => return None,
+        };
+        let length = utf8_sequence_length(lead);
+
+        let mut result = Vec::with_capacity(length);
+        result.push(lead);
+
+        while result.len() < length {
+            match self.next_byte(input) {
+                Some(byte) if byte >> 6 == 0b10 => result.push(byte),
+                Some(byte) => {
+                    self.pending_byte = Some(byte);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        Some(result)
+    }
+
+    fn next_byte<R>(&mut self, input: &mut BitReader<R>) -> Option<u8>
+        where R: Read
+    {
+        match self.pending_byte.take() {
+            Some(byte) => Some(byte),
+            None => input.read_u8().ok(),
+        }
+    }
+}
+
+include!("huffman_dictionary.rs");
 include!("huffman_encoder.rs");
+include!("huffman_stats.rs");
 include!("huffman_decoder.rs");
 include!("huffman_tests.rs");
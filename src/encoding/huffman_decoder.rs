@@ -6,13 +6,17 @@ pub struct HuffmanDecoder<R: Read + Seek> {
 
 impl<R: Read + Seek> HuffmanDecoder<R> {
     pub fn new(input: R) -> Result<Self> {
+        Self::with_dictionaries(input, &[])
+    }
+
+    pub fn with_dictionaries(input: R, dictionaries: &[HuffmanDictionary]) -> Result<Self> {
         let mut result = HuffmanDecoder {
             input: BitReader::new(input),
             code_to_char: HashMap::new(),
             data_offset_bit: 0,
         };
 
-        if result.read_header().is_err() {
+        if result.read_header(dictionaries).is_err() {
             let e = Error::new(ErrorKind::InvalidInput, "Failed to read the header");
             Err(e)
         } else {
@@ -61,42 +65,27 @@ impl<R: Read + Seek> HuffmanDecoder<R> {
         &mut self.input
     }
 
-    fn read_header(&mut self) -> Result<()> {
+    fn read_header(&mut self, dictionaries: &[HuffmanDictionary]) -> Result<()> {
         let dict_length: DictLength = try!(self.input.read_u16());
-        let dict_length = dict_length as usize;
-        self.code_to_char.reserve(dict_length);
-
-        for _ in 0..dict_length {
-            let data_with_marker = try!(self.input.read_u16());
-            let (code_data, code_length) = Self::unpack_data(data_with_marker);
-            let char_length = try!(self.input.read_u8()) as usize;
-            match read_char(&mut self.input, char_length) {
-                Some(ch) => {
-                    let code = Code {
-                        length: code_length,
-                        data: code_data,
-                    };
-                    self.code_to_char.insert(code, ch);
+
+        self.code_to_char = if dict_length == SHARED_DICTIONARY_MARKER {
+            let id = try!(self.input.read_u32());
+            match dictionaries.iter().find(|dictionary| dictionary.id == id) {
+                Some(dictionary) => dictionary.to_code_to_char(),
+                None => {
+                    let e = Error::new(ErrorKind::NotFound, "Unknown dictionary");
+                    return Err(e);
                 }
-                None => unreachable!(),
             }
-        }
+        } else {
+            try!(read_dictionary_entries(&mut self.input, dict_length))
+        };
 
         self.data_offset_bit = self.input.position();
 
         Ok(())
     }
 
-    fn unpack_data(data_with_marker: CodeData) -> (CodeData, CodeLength) {
-        let size = mem::size_of::<CodeData>() * 8;
-        let length = size - data_with_marker.leading_zeros() as usize - 1;
-        let length = length as u8;
-        let shifted_one = 1 << length;
-        let mask = !shifted_one;
-        let data = data_with_marker & mask;
-        (data, length)
-    }
-
     fn decode_char(&mut self) -> Option<CharSlice> {
         let mut code = Code {
             length: 0,
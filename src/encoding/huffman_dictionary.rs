@@ -0,0 +1,212 @@
+pub type DictionaryId = u32;
+
+const SHARED_DICTIONARY_MARKER: DictLength = 0xffff;
+const WIDE_CODES_MARKER: DictLength = 0xfffe;
+
+// codes which fit into 16-bit words with their marker bit are written as such,
+// so headers stay the same as before longer codes were supported
+const NARROW_CODE_LENGTH: CodeLength = 15;
+
+const SYMBOLS_BYTES: u8 = 0;
+const SYMBOLS_CODE_POINTS: u8 = 1;
+const SYMBOLS_WORDS: u8 = 2;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HuffmanDictionary {
+    id: DictionaryId,
+    symbols: Symbols,
+    char_to_code: HashMap<Char, Code>,
+}
+
+impl HuffmanDictionary {
+    pub fn train<R>(id: DictionaryId, symbols: Symbols, samples: Vec<R>) -> Result<Self>
+        where R: Read
+    {
+        let mut trainer = HuffmanEncoder::with_symbols(io::sink(), symbols);
+        for sample in samples {
+            let _ = try!(trainer.analyze(sample));
+        }
+        Ok(trainer.train_finish(id))
+    }
+
+    pub fn id(&self) -> DictionaryId {
+        self.id
+    }
+
+    pub fn symbols(&self) -> Symbols {
+        self.symbols
+    }
+
+    pub fn len(&self) -> usize {
+        self.char_to_code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.char_to_code.is_empty()
+    }
+
+    pub fn write_to<W>(&self, output: W) -> Result<()>
+        where W: Write
+    {
+        let mut output = BitWriter::new(output);
+        try!(output.write_u32(self.id));
+
+        match self.symbols {
+            Symbols::Bytes(max_char_length) => {
+                if max_char_length == 0 || max_char_length > 255 {
+                    let e = Error::new(ErrorKind::InvalidInput, "Invalid symbol length");
+                    return Err(e);
+                }
+                try!(output.write_u8(SYMBOLS_BYTES));
+                try!(output.write_u8(max_char_length as u8));
+            }
+            Symbols::CodePoints => try!(output.write_u8(SYMBOLS_CODE_POINTS)),
+            Symbols::Words => try!(output.write_u8(SYMBOLS_WORDS)),
+        }
+
+        try!(write_dictionary(&mut output, &self.char_to_code));
+        output.flush()
+    }
+
+    pub fn read_from<R>(input: R) -> Result<Self>
+        where R: Read
+    {
+        let mut input = BitReader::new(input);
+        let id = try!(input.read_u32());
+
+        let symbols = match try!(input.read_u8()) {
+            SYMBOLS_BYTES => {
+                match try!(input.read_u8()) {
+                    0 => {
+                        let e = Error::new(ErrorKind::InvalidData, "Invalid symbol length");
+                        return Err(e);
+                    }
+                    max_char_length => Symbols::Bytes(max_char_length as usize),
+                }
+            }
+            SYMBOLS_CODE_POINTS => Symbols::CodePoints,
+            SYMBOLS_WORDS => Symbols::Words,
+            _ => {
+                let e = Error::new(ErrorKind::InvalidData, "Unknown symbols type");
+                return Err(e);
+            }
+        };
+
+        let char_to_code = try!(read_dictionary(&mut input))
+            .into_iter()
+            .map(|(code, ch)| (ch, code))
+            .collect();
+
+        let result = HuffmanDictionary {
+            id: id,
+            symbols: symbols,
+            char_to_code: char_to_code,
+        };
+        Ok(result)
+    }
+
+    fn to_code_to_char(&self) -> HashMap<Code, Char> {
+        self.char_to_code
+            .iter()
+            .map(|(ch, code)| (code.clone(), ch.clone()))
+            .collect()
+    }
+}
+
+fn write_dictionary<W>(output: &mut BitWriter<W>, char_to_code: &HashMap<Char, Code>) -> Result<()>
+    where W: Write
+{
+    assert!(char_to_code.len() < WIDE_CODES_MARKER as usize);
+    let dict_length = char_to_code.len() as DictLength;
+    let wide = char_to_code.values().any(|code| code.length > NARROW_CODE_LENGTH);
+    if wide {
+        try!(output.write_u16(WIDE_CODES_MARKER));
+    }
+    try!(output.write_u16(dict_length));
+
+    for (ch, code) in char_to_code {
+        let data_with_marker: CodeData = pack_data(code);
+        assert!(code.data != data_with_marker);
+        if wide {
+            try!(output.write_u32(data_with_marker));
+        } else {
+            try!(output.write_u16(data_with_marker as u16));
+        }
+        try!(output.write_u8(ch.len() as u8));
+        for i in ch.iter() {
+            try!(output.write_u8(*i));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_dictionary<R>(input: &mut BitReader<R>) -> Result<HashMap<Code, Char>>
+    where R: Read
+{
+    let dict_length: DictLength = try!(input.read_u16());
+    read_dictionary_entries(input, dict_length)
+}
+
+// `dict_length` is the first word of the dictionary, which may be WIDE_CODES_MARKER
+fn read_dictionary_entries<R>(input: &mut BitReader<R>,
+                              dict_length: DictLength)
+                              -> Result<HashMap<Code, Char>>
+    where R: Read
+{
+    let wide = dict_length == WIDE_CODES_MARKER;
+    let (dict_length, max_length) = if wide {
+        (try!(input.read_u16()), max_code_length())
+    } else {
+        (dict_length, NARROW_CODE_LENGTH)
+    };
+    let mut code_to_char = HashMap::with_capacity(dict_length as usize);
+
+    for _ in 0..dict_length {
+        let data_with_marker = if wide {
+            try!(input.read_u32())
+        } else {
+            try!(input.read_u16()) as CodeData
+        };
+        if data_with_marker == 0 {
+            let e = Error::new(ErrorKind::InvalidData, "Invalid code");
+            return Err(e);
+        }
+        let (code_data, code_length) = unpack_data(data_with_marker);
+        if code_length > max_length {
+            let e = Error::new(ErrorKind::InvalidData, "Code is too long");
+            return Err(e);
+        }
+        let char_length = try!(input.read_u8()) as usize;
+        match read_char(input, char_length) {
+            Some(ref ch) if ch.len() == char_length => {
+                let code = Code {
+                    length: code_length,
+                    data: code_data,
+                };
+                code_to_char.insert(code, ch.clone());
+            }
+            _ => {
+                let e = Error::new(ErrorKind::UnexpectedEof, "Truncated dictionary");
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(code_to_char)
+}
+
+fn pack_data(code: &Code) -> CodeData {
+    let shifted_one = 1 << code.length;
+    code.data | shifted_one
+}
+
+fn unpack_data(data_with_marker: CodeData) -> (CodeData, CodeLength) {
+    let size = mem::size_of::<CodeData>() * 8;
+    let length = size - data_with_marker.leading_zeros() as usize - 1;
+    let length = length as u8;
+    let shifted_one = 1 << length;
+    let mask = !shifted_one;
+    let data = data_with_marker & mask;
+    (data, length)
+}
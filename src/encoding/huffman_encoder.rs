@@ -3,29 +3,53 @@ pub struct HuffmanEncoder<W: Write> {
     output: BitWriter<W>,
     char_to_code: HashMap<Char, Code>,
     char_to_weight: HashMap<Char, u64>,
-    max_char_length: usize,
+    symbols: Symbols,
+    header_length_bits: u64,
 }
 
 impl<W: Write> HuffmanEncoder<W> {
     pub fn new(output: W, max_char_length: usize) -> Self {
+        Self::with_symbols(output, Symbols::Bytes(max_char_length))
+    }
+
+    pub fn with_symbols(output: W, symbols: Symbols) -> Self {
         HuffmanEncoder {
             state: State::Initial,
             output: BitWriter::new(output),
             char_to_code: HashMap::new(),
             char_to_weight: HashMap::new(),
-            max_char_length: max_char_length,
+            symbols: symbols,
+            header_length_bits: 0,
         }
     }
 
+    pub fn with_dictionary(output: W, dictionary: &HuffmanDictionary) -> Result<Self> {
+        let mut result = HuffmanEncoder {
+            state: State::Analyzed,
+            output: BitWriter::new(output),
+            char_to_code: dictionary.char_to_code.clone(),
+            char_to_weight: HashMap::new(),
+            symbols: dictionary.symbols,
+            header_length_bits: 0,
+        };
+
+        try!(result.output.write_u16(SHARED_DICTIONARY_MARKER));
+        try!(result.output.write_u32(dictionary.id));
+        result.header_length_bits = result.output.position();
+
+        Ok(result)
+    }
+
     pub fn analyze<R>(&mut self, input: R) -> Result<u64>
         where R: Read
     {
         assert_eq!(State::Initial, self.state);
 
         let mut input = BitReader::new(input);
+        let mut tokenizer = Tokenizer::new(self.symbols);
         let mut bytes_read = 0;
 
-        while let Some(ch) = read_char(&mut input, self.max_char_length) {
+        while let Some(ch) = tokenizer.next_char(&mut input) {
             self.char_to_weight.entry(ch.clone()).or_insert(0);
             self.char_to_weight.get_mut(&ch).map(|mut w| *w += 1);
             bytes_read += ch.len() as u64;
@@ -38,10 +62,24 @@ impl<W: Write> HuffmanEncoder<W> {
         assert_eq!(State::Initial, self.state);
         self.state = State::Analyzed;
 
+        self.limit_words();
+        self.limit_symbols();
         let leaves = self.compute_leaves();
         let tree = self.build_tree(leaves);
         self.build_dictionary(tree);
-        self.write_header()
+        try!(self.write_header());
+        self.header_length_bits = self.output.position();
+
+        Ok(())
+    }
+
+    pub fn stats(&self) -> HuffmanStats {
+        assert!(self.state != State::Initial);
+        // encoders created from a shared dictionary don't know the symbol weights
+        assert!(self.char_to_code.is_empty() || !self.char_to_weight.is_empty());
+        HuffmanStats::new(&self.char_to_code,
+                          &self.char_to_weight,
+                          self.header_length_bits)
     }
 
     pub fn compress<R>(&mut self, input: R) -> Result<u64>
@@ -50,18 +88,11 @@ impl<W: Write> HuffmanEncoder<W> {
         assert_eq!(State::Analyzed, self.state);
 
         let mut input = BitReader::new(input);
+        let mut tokenizer = Tokenizer::new(self.symbols);
         let mut bits_written = 0;
 
-        while let Some(ch) = read_char(&mut input, self.max_char_length) {
-            let code = self.char_to_code.get(&ch).unwrap();
-
-            assert!(code.length <= max_code_length());
-            for i in 0..code.length {
-                let shifted_one = 1 << i;
-                let data = (code.data & shifted_one) > 0;
-                try!(self.output.write_bit(data));
-                bits_written += 1;
-            }
+        while let Some(ch) = tokenizer.next_char(&mut input) {
+            bits_written += try!(self.write_char(&ch));
         }
 
         Ok(bits_written)
@@ -90,6 +121,117 @@ impl<W: Write> HuffmanEncoder<W> {
         &mut self.output
     }
 
+    fn train_finish(&mut self, id: DictionaryId) -> HuffmanDictionary {
+        assert_eq!(State::Initial, self.state);
+        self.state = State::Analyzed;
+
+        // every single byte gets a code, so symbols missing
+        // from the samples can still be encoded
+        for byte in 0..256 {
+            let _ = self.char_to_weight.entry(vec![byte as u8]).or_insert(1);
+        }
+
+        self.limit_words();
+        self.limit_symbols();
+        let leaves = self.compute_leaves();
+        let tree = self.build_tree(leaves);
+        self.build_dictionary(tree);
+
+        HuffmanDictionary {
+            id: id,
+            symbols: self.symbols,
+            char_to_code: self.char_to_code.clone(),
+        }
+    }
+
+    // symbols missing from the dictionary are written
+    // as code points or, failing that, as single bytes
+    fn write_char(&mut self, ch: CharSlice) -> Result<u64> {
+        if self.char_to_code.contains_key(ch) {
+            return self.write_code(ch);
+        }
+
+        if ch.len() <= 1 {
+            let e = Error::new(ErrorKind::InvalidInput, "Symbol is missing from the dictionary");
+            return Err(e);
+        }
+
+        let mut parts = split_code_points(ch);
+        if parts.len() == 1 {
+            parts = ch.iter().map(|&byte| vec![byte]).collect();
+        }
+
+        let mut bits_written = 0;
+        for part in &parts {
+            bits_written += try!(self.write_char(part));
+        }
+
+        Ok(bits_written)
+    }
+
+    fn write_code(&mut self, ch: CharSlice) -> Result<u64> {
+        let code = self.char_to_code.get(ch).unwrap();
+
+        assert!(code.length <= max_code_length());
+        for i in 0..code.length {
+            let shifted_one = 1 << i;
+            let data = (code.data & shifted_one) > 0;
+            try!(self.output.write_bit(data));
+        }
+
+        Ok(code.length as u64)
+    }
+
+    // rare words are replaced with their code points
+    // to keep the dictionary and the codes short
+    fn limit_words(&mut self) {
+        if self.symbols != Symbols::Words {
+            return;
+        }
+
+        let mut words = self.char_to_weight
+            .iter()
+            .filter(|&(ch, _)| split_code_points(ch).len() > 1)
+            .map(|(ch, &weight)| (ch.clone(), weight))
+            .collect::<Vec<(Char, u64)>>();
+
+        if words.len() <= MAX_WORDS {
+            return;
+        }
+
+        words.sort_by(|a, b| b.1.cmp(&a.1));
+
+        for (word, weight) in words.into_iter().skip(MAX_WORDS) {
+            let _ = self.char_to_weight.remove(&word);
+            for code_point in split_code_points(&word) {
+                *self.char_to_weight.entry(code_point).or_insert(0) += weight;
+            }
+        }
+    }
+
+    // rare symbols are replaced with their bytes, so any input
+    // fits into a dictionary of MAX_SYMBOLS entries
+    fn limit_symbols(&mut self) {
+        if self.char_to_weight.len() <= MAX_SYMBOLS {
+            return;
+        }
+
+        let mut symbols = self.char_to_weight
+            .iter()
+            .filter(|&(ch, _)| ch.len() > 1)
+            .map(|(ch, &weight)| (ch.clone(), weight))
+            .collect::<Vec<(Char, u64)>>();
+
+        symbols.sort_by(|a, b| b.1.cmp(&a.1));
+
+        for (symbol, weight) in symbols.into_iter().skip(MAX_SYMBOLS - 256) {
+            let _ = self.char_to_weight.remove(&symbol);
+            for &byte in &symbol {
+                *self.char_to_weight.entry(vec![byte]).or_insert(0) += weight;
+            }
+        }
+    }
+
     fn compute_leaves(&mut self) -> Vec<Tree> {
         let mut leaves: Vec<Tree> = Vec::with_capacity(self.char_to_weight.len());
 
@@ -186,6 +328,7 @@ impl<W: Write> HuffmanEncoder<W> {
         }
 
         assert!(self.char_to_code.len() <= self.max_possible_chars());
+        assert!(self.char_to_code.len() <= MAX_SYMBOLS);
     }
 
     fn compute_code(&self, ch: CharSlice, tree: &Tree) -> Code {
@@ -223,30 +366,16 @@ impl<W: Write> HuffmanEncoder<W> {
     }
 
     fn write_header(&mut self) -> Result<()> {
-        let dict_length = self.char_to_code.len() as DictLength;
-        try!(self.output.write_u16(dict_length));
-
-        for (ref ch, code) in &self.char_to_code {
-            let data_with_marker: CodeData = Self::pack_data(&code);
-            assert!(code.data != data_with_marker);
-            try!(self.output.write_u16(data_with_marker));
-            try!(self.output.write_u8(ch.len() as u8));
-            for i in ch.iter() {
-                try!(self.output.write_u8(*i));
-            }
-        }
-
-        Ok(())
-    }
-
-    fn pack_data(code: &Code) -> CodeData {
-        let shifted_one = 1 << code.length;
-        let result = code.data | shifted_one;
-        result
+        write_dictionary(&mut self.output, &self.char_to_code)
     }
 
     fn max_possible_chars(&self) -> usize {
-        1 << (self.max_char_length * 8)
+        match self.symbols {
+            Symbols::Bytes(max_char_length) if max_char_length < mem::size_of::<usize>() => {
+                1 << (max_char_length * 8)
+            }
+            _ => usize::max_value(),
+        }
     }
 }
 
@@ -0,0 +1,79 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct CodeStats {
+    pub symbol: Vec<u8>,
+    pub weight: u64,
+    pub length: u8,
+    pub data: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HuffmanStats {
+    pub codes: Vec<CodeStats>,
+    pub symbols: u64,
+    pub input_bits: u64,
+    pub header_bits: u64,
+    pub compressed_bits: u64,
+    pub entropy: f64,
+    pub average_code_length: f64,
+}
+
+impl CodeStats {
+    pub fn code_to_string(&self) -> String {
+        (0..self.length)
+            .map(|i| if self.data & (1 << i) > 0 { '1' } else { '0' })
+            .collect()
+    }
+}
+
+impl HuffmanStats {
+    fn new(char_to_code: &HashMap<Char, Code>,
+           char_to_weight: &HashMap<Char, u64>,
+           header_bits: u64)
+           -> Self {
+        let mut codes = char_to_code.iter()
+            .map(|(ch, code)| {
+                CodeStats {
+                    symbol: ch.clone(),
+                    weight: *char_to_weight.get(ch).unwrap_or(&0),
+                    length: code.length,
+                    data: code.data,
+                }
+            })
+            .collect::<Vec<CodeStats>>();
+        codes.sort_by(|a, b| (b.weight, &a.symbol).cmp(&(a.weight, &b.symbol)));
+
+        let symbols = codes.iter().map(|i| i.weight).sum::<u64>();
+        let input_bits = codes.iter().map(|i| i.weight * i.symbol.len() as u64 * 8).sum();
+        let compressed_bits = codes.iter().map(|i| i.weight * i.length as u64).sum::<u64>();
+
+        let mut entropy = 0.0;
+        for i in codes.iter().filter(|i| i.weight > 0) {
+            let probability = i.weight as f64 / symbols as f64;
+            entropy -= probability * probability.log2();
+        }
+
+        let average_code_length = if symbols > 0 {
+            compressed_bits as f64 / symbols as f64
+        } else {
+            0.0
+        };
+
+        HuffmanStats {
+            codes: codes,
+            symbols: symbols,
+            input_bits: input_bits,
+            header_bits: header_bits,
+            compressed_bits: compressed_bits,
+            entropy: entropy,
+            average_code_length: average_code_length,
+        }
+    }
+
+    pub fn entropy_bound_bits(&self) -> f64 {
+        self.entropy * self.symbols as f64
+    }
+
+    pub fn total_bits(&self) -> u64 {
+        self.header_bits + self.compressed_bits
+    }
+}
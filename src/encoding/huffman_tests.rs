@@ -2,8 +2,9 @@
 mod tests {
     extern crate rand;
 
+    use std::char;
     use std::collections::HashSet;
-    use std::io::{Cursor, Write};
+    use std::io::{Cursor, ErrorKind, Write};
     use super::*;
     use super::{NodeData, Tree};
 
@@ -37,6 +38,119 @@ mod tests {
         fn random_multiple(inputs: Vec<Vec<u8>>) -> bool {
             check_multiple(inputs)
         }
+
+        fn random_code_points(text: String, data: Vec<u8>) -> bool {
+            check_symbols(text.as_bytes(), Symbols::CodePoints) &&
+            check_symbols(&data[..], Symbols::CodePoints)
+        }
+
+        fn random_words(text: String, data: Vec<u8>) -> bool {
+            check_symbols(text.as_bytes(), Symbols::Words) &&
+            check_symbols(&data[..], Symbols::Words)
+        }
+    }
+
+    #[test]
+    fn code_points() {
+        assert!(check_symbols(INPUT_TEXT.as_bytes(), Symbols::CodePoints));
+        assert!(check_symbols("Привет, Раст! こんにちは 😀".as_bytes(), Symbols::CodePoints));
+        assert!(check_symbols(&[0xd0, 0x9f, 0xd0, 0x41, 0xff, 0xe3], Symbols::CodePoints));
+        assert!(check_symbols(&[], Symbols::CodePoints));
+    }
+
+    #[test]
+    fn words() {
+        assert!(check_symbols(INPUT_TEXT.as_bytes(), Symbols::Words));
+        assert!(check_symbols("Привет,  Раст!\n\tこんにちは 😀".as_bytes(), Symbols::Words));
+        assert!(check_symbols(&[0xd0, 0x9f, 0xd0, 0x41, 0xff, 0xe3], Symbols::Words));
+        assert!(check_symbols(&[], Symbols::Words));
+
+        let long_word = vec![b'a'; 1000];
+        assert!(check_symbols(&long_word[..], Symbols::Words));
+    }
+
+    #[test]
+    fn too_many_words() {
+        let mut text = String::new();
+        for i in 0..(2 * MAX_WORDS) {
+            text.push_str(&format!("word{} ", i));
+        }
+
+        let input_slice = text.as_bytes();
+        assert!(check_symbols(input_slice, Symbols::Words));
+
+        let mut coder = HuffmanEncoder::with_symbols(vec![], Symbols::Words);
+        let _ = coder.analyze(input_slice).unwrap();
+        coder.analyze_finish().unwrap();
+        assert!(coder.char_to_code.len() <= MAX_WORDS + 256);
+    }
+
+    #[test]
+    fn too_many_code_points() {
+        let text = (0x10000..0x10000 + 70000)
+            .filter_map(char::from_u32)
+            .collect::<String>();
+
+        let input_slice = text.as_bytes();
+        assert!(check_symbols(input_slice, Symbols::CodePoints));
+
+        let mut coder = HuffmanEncoder::with_symbols(vec![], Symbols::CodePoints);
+        let _ = coder.analyze(input_slice).unwrap();
+        coder.analyze_finish().unwrap();
+        assert!(coder.char_to_code.len() <= MAX_SYMBOLS);
+    }
+
+    #[test]
+    fn words_compress_better() {
+        let mut text = String::new();
+        for _ in 0..100 {
+            text.push_str("the quick brown fox jumps over the lazy dog\n");
+        }
+
+        let compressed_length = |symbols| {
+            let mut coder = HuffmanEncoder::with_symbols(vec![], symbols);
+            let _ = coder.analyze(text.as_bytes()).unwrap();
+            coder.analyze_finish().unwrap();
+            coder.compress(text.as_bytes()).unwrap()
+        };
+
+        assert!(compressed_length(Symbols::Words) < compressed_length(Symbols::Bytes(1)));
+        assert!(compressed_length(Symbols::Words) < compressed_length(Symbols::CodePoints));
+    }
+
+    #[test]
+    fn narrow_codes() {
+        // written before codes were widened to 32 bits
+        let data = [0x00, 0x08, 0x00, 0x09, 0x01, 0x76, 0x00, 0x0e, 0x01, 0x73, 0x00, 0x1f,
+                    0x01, 0x20, 0x00, 0x04, 0x01, 0x69, 0x00, 0x17, 0x01, 0x6d, 0x00, 0x0a,
+                    0x01, 0x72, 0x00, 0x0d, 0x01, 0x65, 0x00, 0x0b, 0x01, 0x70, 0x87, 0x8d,
+                    0xcd, 0xc6, 0x8b, 0x54];
+        let mut decoder = HuffmanDecoder::new(Cursor::new(&data[..])).unwrap();
+        let data_offset_bit = decoder.data_offset_bit();
+        let mut decoded = vec![];
+        let length_bits = INPUT_TEXT.len() as u64 * 8;
+        let _ = decoder.decode(&mut decoded, data_offset_bit, length_bits).unwrap();
+        assert_eq!(INPUT_TEXT.as_bytes(), decoded.as_slice());
+
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(INPUT_TEXT.as_bytes()).unwrap();
+        coder.analyze_finish().unwrap();
+        assert_eq!(data_offset_bit, coder.position());
+    }
+
+    #[test]
+    fn wide_codes() {
+        // doubling weights give each next symbol a one bit longer code
+        let mut input = vec![];
+        for byte in 0..17 {
+            input.extend(vec![byte; 1 << byte]);
+        }
+        assert!(check_data(&input[..]));
+
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(&input[..]).unwrap();
+        coder.analyze_finish().unwrap();
+        assert_eq!(&[0xff, 0xfe], &coder.get_output_ref()[..2]);
     }
 
     fn check_data(input_slice: &[u8]) -> bool {
@@ -79,6 +193,27 @@ mod tests {
         true
     }
 
+    fn check_symbols(input_slice: &[u8], symbols: Symbols) -> bool {
+        let mut coder = HuffmanEncoder::with_symbols(vec![], symbols);
+        let original_length_bits = input_slice.len() as u64 * 8;
+        let analyzed_length_bits = coder.analyze(input_slice).unwrap();
+        assert_eq!(original_length_bits, analyzed_length_bits);
+        coder.analyze_finish().unwrap();
+        let data_offset_bit = coder.position();
+
+        let _ = coder.compress(input_slice).unwrap();
+        coder.compress_finish().unwrap();
+
+        let compressed = Cursor::new(coder.get_output_ref().as_slice());
+        let mut decoded = vec![];
+        let decoded_length_bits = HuffmanDecoder::new(compressed)
+            .unwrap()
+            .decode(decoded.by_ref(), data_offset_bit, original_length_bits)
+            .unwrap();
+
+        original_length_bits == decoded_length_bits && input_slice == decoded.as_slice()
+    }
+
     fn assert_data(data: &[u8]) {
         assert!(check_data(data));
     }
@@ -142,6 +277,195 @@ mod tests {
         true
     }
 
+    #[test]
+    fn shared_dictionary() {
+        let samples = vec![&br#"{"id": 1, "name": "mississippi"}"#[..],
+                           &br#"{"id": 2, "name": "river"}"#[..]];
+        let dictionary = HuffmanDictionary::train(7, Symbols::Words, samples).unwrap();
+        assert_eq!(7, dictionary.id());
+        assert_eq!(Symbols::Words, dictionary.symbols());
+
+        let inputs: Vec<&[u8]> = vec![br#"{"id": 3, "name": "river"}"#,
+                                      "{\"unseen\": \"Привет\u{0}\"}".as_bytes(),
+                                      &[0xd0, 0xff, 0x41],
+                                      b""];
+        for input_slice in inputs {
+            assert!(check_dictionary(input_slice, &dictionary));
+        }
+    }
+
+    #[test]
+    fn shared_dictionary_is_smaller() {
+        let input_slice = INPUT_TEXT.as_bytes();
+        let dictionary = HuffmanDictionary::train(1, Symbols::Bytes(1), vec![input_slice])
+            .unwrap();
+
+        let mut coder = HuffmanEncoder::with_dictionary(vec![], &dictionary).unwrap();
+        let _ = coder.compress(input_slice).unwrap();
+        coder.compress_finish().unwrap();
+        let shared_length = coder.get_output_ref().len();
+
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(input_slice).unwrap();
+        coder.analyze_finish().unwrap();
+        let _ = coder.compress(input_slice).unwrap();
+        coder.compress_finish().unwrap();
+
+        assert!(shared_length < coder.get_output_ref().len());
+    }
+
+    #[test]
+    fn missing_dictionary() {
+        let dictionary = HuffmanDictionary::train(1, Symbols::Bytes(1), vec![&b"12"[..]]).unwrap();
+        let other_dictionary = HuffmanDictionary::train(2, Symbols::Bytes(1), vec![&b"12"[..]])
+            .unwrap();
+
+        let mut coder = HuffmanEncoder::with_dictionary(vec![], &dictionary).unwrap();
+        let _ = coder.compress(&b"21"[..]).unwrap();
+        coder.compress_finish().unwrap();
+        let compressed = coder.get_output_ref().as_slice();
+
+        assert!(HuffmanDecoder::new(Cursor::new(compressed)).is_err());
+        assert!(HuffmanDecoder::with_dictionaries(Cursor::new(compressed), &[other_dictionary])
+            .is_err());
+        assert!(HuffmanDecoder::with_dictionaries(Cursor::new(compressed), &[dictionary]).is_ok());
+    }
+
+    #[test]
+    fn save_and_load_dictionary() {
+        let symbols = vec![Symbols::Bytes(2), Symbols::CodePoints, Symbols::Words];
+        for symbols in symbols {
+            let dictionary = HuffmanDictionary::train(42, symbols, vec![INPUT_TEXT.as_bytes()])
+                .unwrap();
+
+            let mut saved = vec![];
+            dictionary.write_to(&mut saved).unwrap();
+            let loaded = HuffmanDictionary::read_from(&saved[..]).unwrap();
+            assert_eq!(dictionary, loaded);
+
+            assert!(HuffmanDictionary::read_from(&saved[..saved.len() - 1]).is_err());
+        }
+    }
+
+    #[test]
+    fn invalid_symbol_length() {
+        let input = vec![INPUT_TEXT.as_bytes()];
+        let dictionary = HuffmanDictionary::train(42, Symbols::Bytes(256), input).unwrap();
+        let mut saved = vec![];
+        let e = dictionary.write_to(&mut saved).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, e.kind());
+
+        let input = vec![INPUT_TEXT.as_bytes()];
+        let dictionary = HuffmanDictionary::train(42, Symbols::Bytes(1), input).unwrap();
+        let mut saved = vec![];
+        dictionary.write_to(&mut saved).unwrap();
+        saved[5] = 0;
+        let e = HuffmanDictionary::read_from(&saved[..]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, e.kind());
+    }
+
+    #[test]
+    fn corrupted_dictionary() {
+        let narrow = [0, 0, 0, 1, 1, 0x00, 0x01, 0x00, 0x00, 0x01, b'a'];
+        let e = HuffmanDictionary::read_from(&narrow[..]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, e.kind());
+        assert!(HuffmanDecoder::new(Cursor::new(&narrow[5..])).is_err());
+
+        let wide = [0, 0, 0, 1, 1, 0xff, 0xfe, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, b'a'];
+        let e = HuffmanDictionary::read_from(&wide[..]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, e.kind());
+        assert!(HuffmanDecoder::new(Cursor::new(&wide[5..])).is_err());
+    }
+
+    quickcheck! {
+        fn random_shared_dictionary(samples: Vec<Vec<u8>>, text: Vec<u8>) -> bool {
+            let samples = samples.iter().map(|i| i.as_slice()).collect::<Vec<&[u8]>>();
+            let dictionary = HuffmanDictionary::train(0, Symbols::CodePoints, samples).unwrap();
+            check_dictionary(&text[..], &dictionary)
+        }
+    }
+
+    fn check_dictionary(input_slice: &[u8], dictionary: &HuffmanDictionary) -> bool {
+        let mut coder = HuffmanEncoder::with_dictionary(vec![], dictionary).unwrap();
+        let data_offset_bit = coder.position();
+        let _ = coder.compress(input_slice).unwrap();
+        coder.compress_finish().unwrap();
+
+        let original_length_bits = input_slice.len() as u64 * 8;
+        let compressed = Cursor::new(coder.get_output_ref().as_slice());
+        let mut decoded = vec![];
+        let dictionaries = [dictionary.clone()];
+        let decoded_length_bits = HuffmanDecoder::with_dictionaries(compressed, &dictionaries)
+            .unwrap()
+            .decode(decoded.by_ref(), data_offset_bit, original_length_bits)
+            .unwrap();
+
+        original_length_bits == decoded_length_bits && input_slice == decoded.as_slice()
+    }
+
+    #[test]
+    fn stats() {
+        let input_slice = INPUT_TEXT.as_bytes();
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(input_slice).unwrap();
+        coder.analyze_finish().unwrap();
+        let header_length_bits = coder.position();
+        let compressed_length_bits = coder.compress(input_slice).unwrap();
+
+        let stats = coder.stats();
+        assert_eq!(8, stats.codes.len());
+        assert_eq!(17, stats.symbols);
+        assert_eq!(17 * 8, stats.input_bits);
+        assert_eq!(header_length_bits, stats.header_bits);
+        assert_eq!(compressed_length_bits, stats.compressed_bits);
+        assert_eq!(header_length_bits + compressed_length_bits, stats.total_bits());
+
+        assert_eq!(vec![b'i'], stats.codes[0].symbol);
+        assert_eq!(5, stats.codes[0].weight);
+        assert_eq!(vec![b's'], stats.codes[1].symbol);
+        assert_eq!(4, stats.codes[1].weight);
+
+        assert!((stats.entropy - 2.6987).abs() < 1e-4);
+        assert!(stats.entropy <= stats.average_code_length);
+        assert!(stats.average_code_length < stats.entropy + 1.0);
+        assert!(stats.entropy_bound_bits() <= stats.compressed_bits as f64);
+
+        for code in &stats.codes {
+            let bits = code.code_to_string();
+            assert_eq!(code.length as usize, bits.len());
+            assert!(bits.chars().all(|i| i == '0' || i == '1'));
+        }
+    }
+
+    #[test]
+    fn stats_of_empty_input() {
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        coder.analyze_finish().unwrap();
+
+        let stats = coder.stats();
+        assert!(stats.codes.is_empty());
+        assert_eq!(0, stats.symbols);
+        assert_eq!(0.0, stats.entropy);
+        assert_eq!(0.0, stats.average_code_length);
+    }
+
+    #[test]
+    #[should_panic]
+    fn stats_before_finish() {
+        let coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.stats();
+    }
+
+    #[test]
+    #[should_panic]
+    fn stats_with_dictionary() {
+        let input_slice = INPUT_TEXT.as_bytes();
+        let dictionary = HuffmanDictionary::train(1, Symbols::Bytes(1), vec![input_slice])
+            .unwrap();
+        let coder = HuffmanEncoder::with_dictionary(vec![], &dictionary).unwrap();
+        let _ = coder.stats();
+    }
+
     #[test]
     fn compute_leaves() {
         let text = INPUT_TEXT;
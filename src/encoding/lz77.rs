@@ -0,0 +1,407 @@
+use std::cmp::min;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::usize;
+
+pub const MIN_MATCH_LENGTH: usize = 3;
+pub const MAX_MATCH_LENGTH: usize = 258;
+pub const MAX_WINDOW_SIZE: usize = 1 << 16;
+
+pub const DEFAULT_WINDOW_SIZE: usize = 1 << 15;
+pub const DEFAULT_LOOKAHEAD_SIZE: usize = MAX_MATCH_LENGTH;
+
+const MAX_CHAIN_LENGTH: usize = 256;
+const HASH_BITS: usize = 15;
+const NO_POSITION: usize = usize::MAX;
+
+const TOKENS_PER_FLAGS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Token {
+    Literal(u8),
+    Match { distance: usize, length: usize },
+}
+
+pub struct Lz77Encoder {
+    window_size: usize,
+    lookahead_size: usize,
+}
+
+impl Lz77Encoder {
+    pub fn new(window_size: usize, lookahead_size: usize) -> Self {
+        assert!(window_size > 0 && window_size <= MAX_WINDOW_SIZE);
+        assert!(lookahead_size >= MIN_MATCH_LENGTH && lookahead_size <= MAX_MATCH_LENGTH);
+
+        Lz77Encoder {
+            window_size: window_size,
+            lookahead_size: lookahead_size,
+        }
+    }
+
+    pub fn encode(&self, input: &[u8]) -> Vec<Token> {
+        let mut result = Vec::with_capacity(input.len() / 2);
+        let mut chains = HashChains::new(self.window_size);
+
+        let mut position = 0;
+        while position < input.len() {
+            let (distance, length) = self.find_match(input, position, &chains);
+
+            if length >= MIN_MATCH_LENGTH {
+                result.push(Token::Match {
+                    distance: distance,
+                    length: length,
+                });
+                for i in position..(position + length) {
+                    chains.insert(input, i);
+                }
+                position += length;
+            } else {
+                result.push(Token::Literal(input[position]));
+                chains.insert(input, position);
+                position += 1;
+            }
+        }
+
+        result
+    }
+
+    fn find_match(&self, input: &[u8], position: usize, chains: &HashChains) -> (usize, usize) {
+        let max_length = min(self.lookahead_size, input.len() - position);
+        if max_length < MIN_MATCH_LENGTH {
+            return (0, 0);
+        }
+
+        let mut best_distance = 0;
+        let mut best_length = 0;
+
+        let mut candidate = chains.head(input, position);
+        let mut chain_length = 0;
+
+        while candidate != NO_POSITION && chain_length < MAX_CHAIN_LENGTH {
+            let distance = position - candidate;
+            if distance > self.window_size {
+                break;
+            }
+
+            let length = input[candidate..]
+                .iter()
+                .zip(input[position..(position + max_length)].iter())
+                .take_while(|&(a, b)| a == b)
+                .count();
+
+            if length > best_length {
+                best_distance = distance;
+                best_length = length;
+                if length == max_length {
+                    break;
+                }
+            }
+
+            let next = chains.previous(candidate);
+            if next >= candidate {
+                break;
+            }
+            candidate = next;
+            chain_length += 1;
+        }
+
+        (best_distance, best_length)
+    }
+}
+
+impl Default for Lz77Encoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SIZE, DEFAULT_LOOKAHEAD_SIZE)
+    }
+}
+
+struct HashChains {
+    heads: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl HashChains {
+    fn new(window_size: usize) -> Self {
+        HashChains {
+            heads: vec![NO_POSITION; 1 << HASH_BITS],
+            previous: vec![NO_POSITION; window_size],
+        }
+    }
+
+    fn insert(&mut self, input: &[u8], position: usize) {
+        if position + MIN_MATCH_LENGTH <= input.len() {
+            let hash = Self::hash(&input[position..]);
+            let index = position % self.previous.len();
+            self.previous[index] = self.heads[hash];
+            self.heads[hash] = position;
+        }
+    }
+
+    fn head(&self, input: &[u8], position: usize) -> usize {
+        self.heads[Self::hash(&input[position..])]
+    }
+
+    fn previous(&self, position: usize) -> usize {
+        self.previous[position % self.previous.len()]
+    }
+
+    fn hash(data: &[u8]) -> usize {
+        let value = ((data[0] as usize) << 16) | ((data[1] as usize) << 8) | (data[2] as usize);
+        (value.wrapping_mul(2_654_435_761) >> 7) & ((1 << HASH_BITS) - 1)
+    }
+}
+
+pub fn decode(tokens: &[Token]) -> Result<Vec<u8>> {
+    let mut result = Vec::with_capacity(tokens.len() * 2);
+
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => result.push(byte),
+            Token::Match { distance, length } => {
+                if distance == 0 || distance > result.len() {
+                    let e = Error::new(ErrorKind::InvalidData, "Invalid match distance");
+                    return Err(e);
+                }
+
+                let start = result.len() - distance;
+                for i in start..(start + length) {
+                    let byte = result[i];
+                    result.push(byte);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// every group of 8 tokens is prefixed by a byte of flags,
+// a set bit means a match stored as distance (u16) and length (u8)
+pub fn write_tokens<W>(tokens: &[Token], output: &mut W) -> Result<()>
+    where W: Write
+{
+    for group in tokens.chunks(TOKENS_PER_FLAGS) {
+        let mut flags = 0u8;
+        let mut data = Vec::with_capacity(1 + 3 * TOKENS_PER_FLAGS);
+        data.push(0);
+
+        for (i, token) in group.iter().enumerate() {
+            match *token {
+                Token::Literal(byte) => data.push(byte),
+                Token::Match { distance, length } => {
+                    assert!(distance > 0 && distance <= MAX_WINDOW_SIZE);
+                    assert!(length >= MIN_MATCH_LENGTH && length <= MAX_MATCH_LENGTH);
+
+                    flags |= 1 << i;
+                    let distance = distance - 1;
+                    data.push((distance >> 8) as u8);
+                    data.push(distance as u8);
+                    data.push((length - MIN_MATCH_LENGTH) as u8);
+                }
+            }
+        }
+
+        data[0] = flags;
+        try!(output.write_all(&data[..]));
+    }
+
+    Ok(())
+}
+
+pub fn read_tokens<R>(input: R) -> Result<Vec<Token>>
+    where R: Read
+{
+    let mut result = vec![];
+    let mut bytes = input.bytes();
+
+    while let Some(flags) = bytes.next() {
+        let flags = try!(flags);
+
+        for i in 0..TOKENS_PER_FLAGS {
+            let is_match = flags & (1 << i) > 0;
+
+            let first = match bytes.next() {
+                Some(byte) => try!(byte),
+                None if is_match => {
+                    let e = Error::new(ErrorKind::UnexpectedEof, "Truncated match");
+                    return Err(e);
+                }
+                None => break,
+            };
+
+            if is_match {
+                let mut next_byte = || -> Result<u8> {
+                    match bytes.next() {
+                        Some(byte) => byte,
+                        None => Err(Error::new(ErrorKind::UnexpectedEof, "Truncated match")),
+                    }
+                };
+                let second = try!(next_byte());
+                let third = try!(next_byte());
+
+                let distance = (((first as usize) << 8) | (second as usize)) + 1;
+                let length = (third as usize) + MIN_MATCH_LENGTH;
+                result.push(Token::Match {
+                    distance: distance,
+                    length: length,
+                });
+            } else {
+                result.push(Token::Literal(first));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::huffman::{HuffmanDecoder, HuffmanEncoder};
+    use std::io::Cursor;
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "mississippi river, mississippi river";
+
+    quickcheck! {
+        fn random_round_trip(input: Vec<u8>) -> bool {
+            check_data(&input[..], Lz77Encoder::default())
+        }
+
+        fn random_small_window(input: Vec<u8>) -> bool {
+            check_data(&input[..], Lz77Encoder::new(4, MIN_MATCH_LENGTH))
+        }
+
+        fn random_repetitive(input: Vec<u8>, repeats: u8) -> bool {
+            let mut data = vec![];
+            for _ in 0..(repeats % 8) {
+                data.extend_from_slice(&input[..]);
+            }
+            check_data(&data[..], Lz77Encoder::new(64, 16))
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let tokens = Lz77Encoder::default().encode(b"abcabcabcd");
+        let expect = vec![Token::Literal(b'a'),
+                          Token::Literal(b'b'),
+                          Token::Literal(b'c'),
+                          Token::Match {
+                              distance: 3,
+                              length: 6,
+                          },
+                          Token::Literal(b'd')];
+        assert_eq!(expect, tokens);
+
+        assert!(check_data(INPUT_TEXT.as_bytes(), Lz77Encoder::default()));
+        assert!(check_data(b"", Lz77Encoder::default()));
+        assert!(check_data(&[0; 1000][..], Lz77Encoder::default()));
+    }
+
+    #[test]
+    fn window_and_lookahead() {
+        let input = (0..2000).map(|i| (i % 100) as u8).collect::<Vec<u8>>();
+
+        let tokens = Lz77Encoder::new(50, 10).encode(&input[..]);
+        assert!(tokens.iter().all(is_literal));
+
+        let tokens = Lz77Encoder::new(100, 10).encode(&input[..]);
+        for token in &tokens {
+            if let Token::Match { distance, length } = *token {
+                assert!(distance <= 100);
+                assert!(length <= 10);
+            }
+        }
+        assert!(tokens.len() < input.len() / 5);
+    }
+
+    #[test]
+    fn invalid_distance() {
+        assert!(decode(&[Token::Match {
+                             distance: 1,
+                             length: 3,
+                         }])
+            .is_err());
+        assert!(decode(&[Token::Literal(1),
+                         Token::Match {
+                             distance: 2,
+                             length: 3,
+                         }])
+            .is_err());
+    }
+
+    #[test]
+    fn truncated_tokens() {
+        let tokens = Lz77Encoder::default().encode(b"abcabcabc");
+        let mut data = vec![];
+        write_tokens(&tokens[..], &mut data).unwrap();
+        assert!(read_tokens(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn huffman_pipeline() {
+        let mut text = String::new();
+        for i in 0..200 {
+            text.push_str(&format!("{} {}\n", INPUT_TEXT, i % 10));
+        }
+        let input_slice = text.as_bytes();
+
+        let tokens = Lz77Encoder::default().encode(input_slice);
+        let mut packed = vec![];
+        write_tokens(&tokens[..], &mut packed).unwrap();
+
+        let huffman_only_length = huffman_compressed_length(input_slice);
+        let pipeline_length = huffman_compressed_length(&packed[..]);
+        assert!(pipeline_length * 10 < huffman_only_length);
+
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(&packed[..]).unwrap();
+        coder.analyze_finish().unwrap();
+        let data_offset_bit = coder.position();
+        let _ = coder.compress(&packed[..]).unwrap();
+        coder.compress_finish().unwrap();
+
+        let compressed = Cursor::new(coder.get_output_ref().as_slice());
+        let mut unpacked = vec![];
+        let _ = HuffmanDecoder::new(compressed)
+            .unwrap()
+            .decode(&mut unpacked, data_offset_bit, packed.len() as u64 * 8)
+            .unwrap();
+
+        let tokens = read_tokens(&unpacked[..]).unwrap();
+        assert_eq!(input_slice, decode(&tokens[..]).unwrap().as_slice());
+    }
+
+    fn huffman_compressed_length(input_slice: &[u8]) -> usize {
+        let mut coder = HuffmanEncoder::new(vec![], 1);
+        let _ = coder.analyze(input_slice).unwrap();
+        coder.analyze_finish().unwrap();
+        let _ = coder.compress(input_slice).unwrap();
+        coder.compress_finish().unwrap();
+        coder.get_output_ref().len()
+    }
+
+    fn is_literal(token: &Token) -> bool {
+        match *token {
+            Token::Literal(_) => true,
+            Token::Match { .. } => false,
+        }
+    }
+
+    fn check_data(input_slice: &[u8], encoder: Lz77Encoder) -> bool {
+        let tokens = encoder.encode(input_slice);
+
+        for token in &tokens {
+            if let Token::Match { distance, length } = *token {
+                if distance > encoder.window_size || length > encoder.lookahead_size {
+                    return false;
+                }
+            }
+        }
+
+        let mut packed = vec![];
+        write_tokens(&tokens[..], &mut packed).unwrap();
+        let unpacked_tokens = read_tokens(&packed[..]).unwrap();
+
+        tokens == unpacked_tokens && decode(&tokens[..]).unwrap().as_slice() == input_slice
+    }
+}
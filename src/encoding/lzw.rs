@@ -0,0 +1,545 @@
+use encoding::bitorder::BitOrder;
+use encoding::bitreader::BitReader;
+use encoding::bitwriter::BitWriter;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+pub const MIN_CODE_BITS: u8 = 9;
+pub const MAX_CODE_BITS: u8 = 16;
+pub const GIF_MAX_CODE_BITS: u8 = 12;
+
+const UNIX_COMPRESS_MAGIC: [u8; 2] = [0x1f, 0x9d];
+const UNIX_COMPRESS_BLOCK_MODE: u8 = 0x80;
+const UNIX_COMPRESS_BITS_MASK: u8 = 0x1f;
+
+// code 1 << literal_bits clears the dictionary, the next one (if enabled) ends the stream
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LzwCodec {
+    bit_order: BitOrder,
+    literal_bits: u8,
+    max_code_bits: u8,
+    end_code: bool,
+    early_change: bool,
+    group_padding: bool,
+}
+
+impl LzwCodec {
+    pub fn new(bit_order: BitOrder, max_code_bits: u8) -> Self {
+        assert!(max_code_bits >= MIN_CODE_BITS && max_code_bits <= MAX_CODE_BITS);
+
+        LzwCodec {
+            bit_order: bit_order,
+            literal_bits: 8,
+            max_code_bits: max_code_bits,
+            end_code: true,
+            early_change: false,
+            group_padding: false,
+        }
+    }
+
+    pub fn gif(literal_bits: u8) -> Self {
+        assert!(literal_bits >= 2 && literal_bits <= 8);
+
+        LzwCodec {
+            bit_order: BitOrder::LsbFirst,
+            literal_bits: literal_bits,
+            max_code_bits: GIF_MAX_CODE_BITS,
+            end_code: true,
+            early_change: false,
+            group_padding: false,
+        }
+    }
+
+    pub fn tiff() -> Self {
+        LzwCodec {
+            bit_order: BitOrder::MsbFirst,
+            literal_bits: 8,
+            max_code_bits: 12,
+            end_code: true,
+            early_change: true,
+            group_padding: false,
+        }
+    }
+
+    // legacy compress(1) writes codes in groups of 8 and pads the current group
+    // every time the code width changes
+    pub fn unix_compress(max_code_bits: u8) -> Self {
+        assert!(max_code_bits >= MIN_CODE_BITS && max_code_bits <= MAX_CODE_BITS);
+
+        LzwCodec {
+            bit_order: BitOrder::LsbFirst,
+            literal_bits: 8,
+            max_code_bits: max_code_bits,
+            end_code: false,
+            early_change: false,
+            group_padding: true,
+        }
+    }
+
+    pub fn bit_order(&self) -> BitOrder {
+        self.bit_order
+    }
+
+    pub fn max_code_bits(&self) -> u8 {
+        self.max_code_bits
+    }
+
+    fn clear_code(&self) -> u32 {
+        1 << self.literal_bits
+    }
+
+    fn end_code(&self) -> u32 {
+        self.clear_code() + 1
+    }
+
+    fn first_code(&self) -> u32 {
+        if self.end_code {
+            self.clear_code() + 2
+        } else {
+            self.clear_code() + 1
+        }
+    }
+
+    fn min_code_bits(&self) -> u8 {
+        self.literal_bits + 1
+    }
+
+    // compress(1) widens 9-bit codes once more when its table fills up, even though
+    // no new codes are added after that
+    fn max_code_width(&self) -> u8 {
+        if self.group_padding && self.max_code_bits == self.min_code_bits() {
+            self.max_code_bits + 1
+        } else {
+            self.max_code_bits
+        }
+    }
+
+    // the width grows once the next free code no longer fits into it
+    fn code_bits_limit(&self, code_bits: u8) -> u32 {
+        if self.early_change {
+            (1 << code_bits) - 1
+        } else {
+            1 << code_bits
+        }
+    }
+
+    pub fn encode<W>(&self, input: &[u8], output: W) -> Result<()>
+        where W: Write
+    {
+        if let Some(&byte) = input.iter().find(|&&byte| (byte as u32) >= self.clear_code()) {
+            let message = format!("Byte {} doesn't fit into {} bits", byte, self.literal_bits);
+            return Err(Error::new(ErrorKind::InvalidInput, message));
+        }
+
+        let mut output = CodeWriter::new(output, self.bit_order);
+        let mut dictionary = HashMap::new();
+        let mut code_bits = self.min_code_bits();
+        let mut next_code = self.first_code();
+        let table_limit = self.code_bits_limit(self.max_code_bits);
+
+        if self.end_code {
+            try!(output.write_code(self.clear_code(), code_bits));
+        }
+
+        let mut prefix: Option<u32> = None;
+        for &byte in input {
+            let current = match prefix {
+                None => {
+                    prefix = Some(byte as u32);
+                    continue;
+                }
+                Some(current) => current,
+            };
+
+            if let Some(&code) = dictionary.get(&(current, byte)) {
+                prefix = Some(code);
+                continue;
+            }
+
+            try!(output.write_code(current, code_bits));
+            if next_code >= self.code_bits_limit(code_bits) && code_bits < self.max_code_width() {
+                try!(self.pad_group(&mut output, code_bits));
+                code_bits += 1;
+            }
+
+            if next_code >= table_limit {
+                try!(output.write_code(self.clear_code(), code_bits));
+                try!(self.pad_group(&mut output, code_bits));
+                dictionary.clear();
+                code_bits = self.min_code_bits();
+                next_code = self.first_code();
+            } else {
+                dictionary.insert((current, byte), next_code);
+                next_code += 1;
+            }
+
+            prefix = Some(byte as u32);
+        }
+
+        if let Some(current) = prefix {
+            try!(output.write_code(current, code_bits));
+            if next_code >= self.code_bits_limit(code_bits) && code_bits < self.max_code_width() {
+                try!(self.pad_group(&mut output, code_bits));
+                code_bits += 1;
+            }
+        }
+
+        if self.end_code {
+            try!(output.write_code(self.end_code(), code_bits));
+        }
+
+        output.finish()
+    }
+
+    pub fn decode<R>(&self, input: R) -> Result<Vec<u8>>
+        where R: Read
+    {
+        let mut input = CodeReader::new(input, self.bit_order);
+        let mut result = vec![];
+        let mut dictionary = Dictionary::new(self.first_code());
+        let mut code_bits = self.min_code_bits();
+        let mut previous: Option<u32> = None;
+        let max_size = 1 << self.max_code_bits;
+
+        while let Some(code) = try!(input.read_code(code_bits)) {
+            if code == self.clear_code() {
+                if !try!(self.skip_group(&mut input, code_bits)) {
+                    break;
+                }
+                dictionary.clear(self.first_code());
+                code_bits = self.min_code_bits();
+                previous = None;
+                continue;
+            }
+
+            if self.end_code && code == self.end_code() {
+                break;
+            }
+
+            let next_code = dictionary.next_code();
+            let start = result.len();
+            if code < next_code && !(code >= self.clear_code() && code < self.first_code()) {
+                dictionary.write_entry(code, &mut result);
+            } else if code == next_code && previous.is_some() {
+                dictionary.write_entry(previous.unwrap(), &mut result);
+                let first_byte = result[start];
+                result.push(first_byte);
+            } else {
+                let e = Error::new(ErrorKind::InvalidData, "Invalid LZW code");
+                return Err(e);
+            }
+
+            if let Some(previous_code) = previous {
+                if next_code < max_size {
+                    dictionary.push(previous_code, result[start]);
+                }
+            }
+            previous = Some(code);
+
+            let next_code = dictionary.next_code();
+            if next_code >= self.code_bits_limit(code_bits) && code_bits < self.max_code_width() {
+                if !try!(self.skip_group(&mut input, code_bits)) {
+                    break;
+                }
+                code_bits += 1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn pad_group<W: Write>(&self, output: &mut CodeWriter<W>, code_bits: u8) -> Result<()> {
+        if self.group_padding {
+            while (output.position() - output.group_start) % (code_bits as u64 * 8) != 0 {
+                try!(output.write_bit(false));
+            }
+            output.group_start = output.position();
+        }
+        Ok(())
+    }
+
+    // returns false when the input ends inside of the padding
+    fn skip_group<R: Read>(&self, input: &mut CodeReader<R>, code_bits: u8) -> Result<bool> {
+        if self.group_padding {
+            while (input.position() - input.group_start) % (code_bits as u64 * 8) != 0 {
+                if try!(input.read_code(1)).is_none() {
+                    return Ok(false);
+                }
+            }
+            input.group_start = input.position();
+        }
+        Ok(true)
+    }
+}
+
+struct Dictionary {
+    first_code: u32,
+    entries: Vec<(u32, u8)>,
+}
+
+impl Dictionary {
+    fn new(first_code: u32) -> Self {
+        Dictionary {
+            first_code: first_code,
+            entries: vec![],
+        }
+    }
+
+    fn clear(&mut self, first_code: u32) {
+        self.first_code = first_code;
+        self.entries.clear();
+    }
+
+    fn next_code(&self) -> u32 {
+        self.first_code + self.entries.len() as u32
+    }
+
+    fn push(&mut self, prefix: u32, byte: u8) {
+        self.entries.push((prefix, byte));
+    }
+
+    fn write_entry(&self, code: u32, output: &mut Vec<u8>) {
+        let start = output.len();
+        let mut code = code;
+        while code >= self.first_code {
+            let (prefix, byte) = self.entries[(code - self.first_code) as usize];
+            output.push(byte);
+            code = prefix;
+        }
+        output.push(code as u8);
+        output[start..].reverse();
+    }
+}
+
+struct CodeWriter<W: Write> {
+    writer: BitWriter<W>,
+    position: u64,
+    group_start: u64,
+}
+
+impl<W: Write> CodeWriter<W> {
+    fn new(output: W, bit_order: BitOrder) -> Self {
+        CodeWriter {
+            writer: BitWriter::with_bit_order(output, bit_order),
+            position: 0,
+            group_start: 0,
+        }
+    }
+
+    fn write_code(&mut self, code: u32, code_bits: u8) -> Result<()> {
+        try!(self.writer.write_bits(code as u64, code_bits));
+        self.position += code_bits as u64;
+        Ok(())
+    }
+
+    fn write_bit(&mut self, data: bool) -> Result<()> {
+        try!(self.writer.write_bit(data));
+        self.position += 1;
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        while self.position % 8 != 0 {
+            try!(self.write_bit(false));
+        }
+        self.writer.flush()
+    }
+}
+
+struct CodeReader<R: Read> {
+    reader: BitReader<R>,
+    position: u64,
+    group_start: u64,
+}
+
+impl<R: Read> CodeReader<R> {
+    fn new(input: R, bit_order: BitOrder) -> Self {
+        CodeReader {
+            reader: BitReader::with_bit_order(input, bit_order),
+            position: 0,
+            group_start: 0,
+        }
+    }
+
+    // the trailing bits of the last byte are padding, so a partial code means the end of input
+    fn read_code(&mut self, code_bits: u8) -> Result<Option<u32>> {
+        match self.reader.read_bits(code_bits) {
+            Ok(code) => {
+                self.position += code_bits as u64;
+                Ok(Some(code as u32))
+            }
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+pub fn compress_unix<W>(input: &[u8], max_code_bits: u8, output: &mut W) -> Result<()>
+    where W: Write
+{
+    try!(output.write_all(&UNIX_COMPRESS_MAGIC));
+    try!(output.write_all(&[UNIX_COMPRESS_BLOCK_MODE | max_code_bits]));
+    LzwCodec::unix_compress(max_code_bits).encode(input, output)
+}
+
+pub fn decompress_unix<R>(mut input: R) -> Result<Vec<u8>>
+    where R: Read
+{
+    let mut header = [0; 3];
+    try!(input.read_exact(&mut header));
+
+    if header[..2] != UNIX_COMPRESS_MAGIC {
+        let e = Error::new(ErrorKind::InvalidData, "Not a compress(1) stream");
+        return Err(e);
+    }
+
+    let max_code_bits = header[2] & UNIX_COMPRESS_BITS_MASK;
+    if (header[2] & UNIX_COMPRESS_BLOCK_MODE) == 0 || max_code_bits < MIN_CODE_BITS ||
+       max_code_bits > MAX_CODE_BITS {
+        let e = Error::new(ErrorKind::InvalidData, "Unsupported compress(1) flags");
+        return Err(e);
+    }
+
+    LzwCodec::unix_compress(max_code_bits).decode(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_TEXT: &'static str = "TOBEORNOTTOBEORTOBEORNOT";
+
+    quickcheck! {
+        fn random_lsb(input: Vec<u8>) -> bool {
+            check_data(&input[..], LzwCodec::new(BitOrder::LsbFirst, MAX_CODE_BITS))
+        }
+
+        fn random_msb(input: Vec<u8>) -> bool {
+            check_data(&input[..], LzwCodec::new(BitOrder::MsbFirst, MAX_CODE_BITS))
+        }
+
+        fn random_small_dictionary(input: Vec<u8>, repeats: u8) -> bool {
+            let mut data = vec![];
+            for _ in 0..(repeats % 16) {
+                data.extend_from_slice(&input[..]);
+            }
+            check_data(&data[..], LzwCodec::new(BitOrder::MsbFirst, MIN_CODE_BITS)) &&
+            check_data(&data[..], LzwCodec::tiff()) &&
+            check_data(&data[..], LzwCodec::unix_compress(MIN_CODE_BITS))
+        }
+
+        fn random_gif(input: Vec<u8>, literal_bits: u8) -> bool {
+            let literal_bits = literal_bits % 7 + 2;
+            let data = input.iter().map(|&byte| byte >> (8 - literal_bits)).collect::<Vec<u8>>();
+            check_data(&data[..], LzwCodec::gif(literal_bits))
+        }
+
+        fn random_unix(input: Vec<u8>) -> bool {
+            let mut data = vec![];
+            compress_unix(&input[..], MAX_CODE_BITS, &mut data).unwrap();
+            decompress_unix(&data[..]).unwrap() == input
+        }
+    }
+
+    #[test]
+    fn known_codes() {
+        let mut data = vec![];
+        LzwCodec::new(BitOrder::MsbFirst, MAX_CODE_BITS).encode(b"ABABABA", &mut data).unwrap();
+        // clear, A, B, AB, ABA, end
+        let expect = [256, 65, 66, 258, 260, 257];
+        assert_eq!(pack_msb(&expect, 9), data);
+
+        let mut data = vec![];
+        LzwCodec::gif(2).encode(&[1, 1, 1, 1, 1, 2], &mut data).unwrap();
+        // clear (4), 1, 6, 6 in 3 bits, then 2 and end (5) in 4 bits
+        assert_eq!(vec![0x8c, 0x2d, 0x05], data);
+    }
+
+    #[test]
+    fn code_width_grows() {
+        let input = (0..2000).map(|i| (i * 7 % 256) as u8).collect::<Vec<u8>>();
+
+        for codec in &[LzwCodec::new(BitOrder::LsbFirst, MAX_CODE_BITS),
+                       LzwCodec::new(BitOrder::MsbFirst, 10),
+                       LzwCodec::tiff(),
+                       LzwCodec::gif(8),
+                       LzwCodec::unix_compress(12)] {
+            assert!(check_data(&input[..], *codec));
+        }
+    }
+
+    #[test]
+    fn compression() {
+        let mut text = String::new();
+        for _ in 0..200 {
+            text.push_str(INPUT_TEXT);
+        }
+
+        let codec = LzwCodec::new(BitOrder::LsbFirst, MAX_CODE_BITS);
+        let mut data = vec![];
+        codec.encode(text.as_bytes(), &mut data).unwrap();
+        assert!(data.len() * 10 < text.len());
+
+        let mut data = vec![];
+        compress_unix(text.as_bytes(), MAX_CODE_BITS, &mut data).unwrap();
+        assert_eq!(vec![0x1f, 0x9d, 0x90], data[..3].to_vec());
+        assert!(data.len() * 10 < text.len());
+    }
+
+    #[test]
+    fn invalid_input() {
+        let mut data = vec![];
+        assert!(LzwCodec::gif(4).encode(&[1, 16], &mut data).is_err());
+
+        let codec = LzwCodec::new(BitOrder::MsbFirst, MAX_CODE_BITS);
+        assert!(codec.decode(&pack_msb(&[256, 300], 9)[..]).is_err());
+        assert!(codec.decode(&pack_msb(&[256, 258], 9)[..]).is_err());
+
+        assert!(decompress_unix(&[0x1f, 0x9d][..]).is_err());
+        assert!(decompress_unix(&[0x1f, 0x8b, 0x90][..]).is_err());
+        assert!(decompress_unix(&[0x1f, 0x9d, 0x11][..]).is_err());
+        assert!(decompress_unix(&[0x1f, 0x9d, 0x88][..]).is_err());
+    }
+
+    #[test]
+    fn unix_9_bits() {
+        // enough distinct pairs to fill the table, so the codes become 10 bits wide
+        let input = (0..5000).map(|i: u32| (i * i % 251) as u8).collect::<Vec<u8>>();
+        let mut data = vec![];
+        compress_unix(&input[..], MIN_CODE_BITS, &mut data).unwrap();
+        assert_eq!(vec![0x1f, 0x9d, 0x89], data[..3].to_vec());
+        assert_eq!(input, decompress_unix(&data[..]).unwrap());
+        assert!(decompress_unix(&[0x1f, 0x9d, 0x89][..]).unwrap().is_empty());
+    }
+
+    fn pack_msb(codes: &[u32], code_bits: u8) -> Vec<u8> {
+        let mut bits = vec![];
+        for &code in codes {
+            for i in (0..code_bits).rev() {
+                bits.push((code >> i) & 1 > 0);
+            }
+        }
+
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk.iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, &bit)| if bit { byte | (0x80 >> i) } else { byte })
+            })
+            .collect()
+    }
+
+    fn check_data(input_slice: &[u8], codec: LzwCodec) -> bool {
+        let mut data = vec![];
+        codec.encode(input_slice, &mut data).unwrap();
+        codec.decode(&data[..]).unwrap().as_slice() == input_slice
+    }
+}
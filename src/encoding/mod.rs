@@ -1,6 +1,19 @@
+pub mod arithmetic;
+pub mod ascii85;
+pub mod base32;
 pub mod base64;
+pub mod binary_to_text;
+pub mod bitorder;
 pub mod bitreader;
 pub mod bitwriter;
+pub mod bwt;
+pub mod checksum;
+pub mod codec;
 pub mod crc;
+pub mod crc_correction;
+pub mod deflate;
+pub mod hex;
 pub mod huffman;
+pub mod lz77;
+pub mod lzw;
 pub mod unicode;
@@ -1,50 +1,286 @@
+use std::{error, fmt, result};
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+pub use self::charsets::{CHARSETS, Charset};
+pub use self::transcoder::Transcoder;
+
+mod charsets;
+pub mod normalization;
+pub mod segmentation;
+mod tables;
+mod transcoder;
+
+pub const REPLACEMENT_CHARACTER: u32 = 0xfffd;
+
+// unpaired surrogates are replaced with U+FFFD
 pub fn to_utf8(utf16: &[u16]) -> Vec<u8> {
-    let mut utf8 = vec![];
-
-    for ch in utf16 {
-        if *ch <= 0x7f {
-            utf8.push(*ch as u8)
-        } else if *ch <= 0x7ff {
-            utf8.push(0b11000000 | (*ch >> 6) as u8);
-            utf8.push(0b10000000 | ((*ch << 10) >> 10) as u8);
-        } else {
-            utf8.push(0b11100000 | (*ch >> 12) as u8);
-            utf8.push(0b10000000 | ((*ch << 4) >> 10) as u8);
-            utf8.push(0b10000000 | ((*ch << 10) >> 10) as u8);
-        }
+    let mut utf8 = Vec::with_capacity(utf16.len());
+    let mut i = 0;
+
+    while i < utf16.len() {
+        let (code_point, length) = decode_utf16(&utf16[i..]);
+        push_utf8(code_point.unwrap_or(REPLACEMENT_CHARACTER), &mut utf8);
+        i += length;
     }
 
     utf8
 }
 
-pub fn to_utf16(utf8: &[u8]) -> Vec<u16> {
-    let mut utf16: Vec<u16> = vec![];
+pub fn try_to_utf8(utf16: &[u16]) -> Result<Vec<u8>> {
+    let mut utf8 = Vec::with_capacity(utf16.len());
+    let mut i = 0;
+
+    while i < utf16.len() {
+        match decode_utf16(&utf16[i..]) {
+            (Some(code_point), length) => {
+                push_utf8(code_point, &mut utf8);
+                i += length;
+            }
+            (None, _) => {
+                let message = format!("Unpaired surrogate 0x{:04x} at {}", utf16[i], i);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+        }
+    }
+
+    Ok(utf8)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Utf8ErrorKind {
+    InvalidLeadByte,
+    InvalidContinuation,
+    Overlong,
+    Surrogate,
+    OutOfRange,
+    Truncated,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Utf8Error {
+    // the number of bytes before the error, all of them valid UTF-8
+    pub offset: usize,
+    pub kind: Utf8ErrorKind,
+    // the length of the invalid sequence, replaced with one U+FFFD in lossy mode
+    pub length: usize,
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.kind {
+            Utf8ErrorKind::InvalidLeadByte => "Invalid UTF-8 lead byte",
+            Utf8ErrorKind::InvalidContinuation => "Invalid UTF-8 continuation byte",
+            Utf8ErrorKind::Overlong => "Overlong UTF-8 sequence",
+            Utf8ErrorKind::Surrogate => "UTF-8 encoded surrogate",
+            Utf8ErrorKind::OutOfRange => "UTF-8 sequence above U+10FFFF",
+            Utf8ErrorKind::Truncated => "Truncated UTF-8 sequence",
+        };
+        write!(f, "{} at {}", description, self.offset)
+    }
+}
+
+impl error::Error for Utf8Error {
+    fn description(&self) -> &str {
+        "invalid UTF-8"
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(error: Utf8Error) -> Error {
+        Error::new(ErrorKind::InvalidData, error)
+    }
+}
+
+pub fn to_utf16(utf8: &[u8]) -> result::Result<Vec<u16>, Utf8Error> {
+    let mut utf16 = Vec::with_capacity(utf8.len());
     let mut i = 0;
 
-    let mut ch;
     while i < utf8.len() {
-        if (utf8[i] >> 7) == 0 {
-            ch = utf8[i] as u16;
-            i += 1;
-        } else if (utf8[i] >> 5) == 0b110 {
-            let high = ((utf8[i] & 0b00111111) as u16) << 6;
-            let low = (utf8[i + 1] & 0b01111111) as u16;
-            ch = high | low;
-            i += 2;
-        } else {
-            let high = ((utf8[i] & 0b00011111) as u16) << 12;
-            let middle = ((utf8[i + 1] & 0b01111111) as u16) << 6;
-            let low = (utf8[i + 2] & 0b01111111) as u16;
-            ch = high | middle | low;
-            i += 3;
+        match decode_utf8(&utf8[i..]) {
+            Ok((ch, length)) => {
+                push_utf16(ch, &mut utf16);
+                i += length;
+            }
+            Err((kind, length)) => {
+                return Err(Utf8Error {
+                    offset: i,
+                    kind: kind,
+                    length: length,
+                })
+            }
         }
+    }
 
-        utf16.push(ch);
+    Ok(utf16)
+}
+
+// like String::from_utf8_lossy, each maximal invalid subsequence becomes one U+FFFD
+pub fn to_utf16_lossy(utf8: &[u8]) -> Vec<u16> {
+    let mut utf16 = Vec::with_capacity(utf8.len());
+    let mut i = 0;
+
+    while i < utf8.len() {
+        let (ch, length) = decode_utf8(&utf8[i..])
+            .unwrap_or_else(|(_, length)| (REPLACEMENT_CHARACTER, length));
+        push_utf16(ch, &mut utf16);
+        i += length;
     }
 
     utf16
 }
 
+// returns the code point and its length, or the error and the length of the invalid prefix
+fn decode_utf8(utf8: &[u8]) -> result::Result<(u32, usize), (Utf8ErrorKind, usize)> {
+    let lead = utf8[0];
+    // the allowed range of the second byte rules out overlong forms, surrogates and
+    // code points above U+10FFFF
+    let (length, second_range, second_error) = match lead {
+        0x00...0x7f => return Ok((lead as u32, 1)),
+        0xc2...0xdf => (2, (0x80, 0xbf), Utf8ErrorKind::InvalidContinuation),
+        0xe0 => (3, (0xa0, 0xbf), Utf8ErrorKind::Overlong),
+        0xed => (3, (0x80, 0x9f), Utf8ErrorKind::Surrogate),
+        0xe1...0xef => (3, (0x80, 0xbf), Utf8ErrorKind::InvalidContinuation),
+        0xf0 => (4, (0x90, 0xbf), Utf8ErrorKind::Overlong),
+        0xf4 => (4, (0x80, 0x8f), Utf8ErrorKind::OutOfRange),
+        0xf1...0xf3 => (4, (0x80, 0xbf), Utf8ErrorKind::InvalidContinuation),
+        0xc0 | 0xc1 => return Err((Utf8ErrorKind::Overlong, 1)),
+        0xf5...0xf7 => return Err((Utf8ErrorKind::OutOfRange, 1)),
+        _ => return Err((Utf8ErrorKind::InvalidLeadByte, 1)),
+    };
+
+    let mut ch = (lead & (0x7f >> length)) as u32;
+    for i in 1..length {
+        let byte = match utf8.get(i) {
+            Some(&byte) => byte,
+            None => return Err((Utf8ErrorKind::Truncated, i)),
+        };
+
+        let (min, max) = if i == 1 { second_range } else { (0x80, 0xbf) };
+        if byte < min || byte > max {
+            let kind = if i == 1 && byte >= 0x80 && byte <= 0xbf {
+                second_error
+            } else {
+                Utf8ErrorKind::InvalidContinuation
+            };
+            return Err((kind, i));
+        }
+        ch = (ch << 6) | (byte & 0b00111111) as u32;
+    }
+
+    Ok((ch, length))
+}
+
+pub fn to_utf32(utf8: &[u8]) -> result::Result<Vec<u32>, Utf8Error> {
+    let mut utf32 = Vec::with_capacity(utf8.len());
+    let mut i = 0;
+
+    while i < utf8.len() {
+        match decode_utf8(&utf8[i..]) {
+            Ok((ch, length)) => {
+                utf32.push(ch);
+                i += length;
+            }
+            Err((kind, length)) => {
+                return Err(Utf8Error {
+                    offset: i,
+                    kind: kind,
+                    length: length,
+                })
+            }
+        }
+    }
+
+    Ok(utf32)
+}
+
+pub fn from_utf32(utf32: &[u32]) -> Result<Vec<u8>> {
+    let mut utf8 = Vec::with_capacity(utf32.len());
+
+    for (i, &ch) in utf32.iter().enumerate() {
+        if !is_scalar_value(ch) {
+            let message = format!("Invalid code point 0x{:x} at {}", ch, i);
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        push_utf8(ch, &mut utf8);
+    }
+
+    Ok(utf8)
+}
+
+fn is_scalar_value(ch: u32) -> bool {
+    ch <= 0x10ffff && (ch < 0xd800 || ch > 0xdfff)
+}
+
+// returns the code point, or None for an unpaired surrogate, and the number of units read
+fn decode_utf16(utf16: &[u16]) -> (Option<u32>, usize) {
+    let first = utf16[0] as u32;
+    match first {
+        0xd800...0xdbff => {
+            match utf16.get(1).map(|&unit| unit as u32) {
+                Some(second @ 0xdc00...0xdfff) => {
+                    (Some(0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)), 2)
+                }
+                _ => (None, 1),
+            }
+        }
+        0xdc00...0xdfff => (None, 1),
+        _ => (Some(first), 1),
+    }
+}
+
+fn push_utf8(ch: u32, utf8: &mut Vec<u8>) {
+    if ch <= 0x7f {
+        utf8.push(ch as u8)
+    } else if ch <= 0x7ff {
+        utf8.push(0b11000000 | (ch >> 6) as u8);
+        utf8.push(0b10000000 | (ch & 0b00111111) as u8);
+    } else if ch <= 0xffff {
+        utf8.push(0b11100000 | (ch >> 12) as u8);
+        utf8.push(0b10000000 | ((ch >> 6) & 0b00111111) as u8);
+        utf8.push(0b10000000 | (ch & 0b00111111) as u8);
+    } else {
+        utf8.push(0b11110000 | (ch >> 18) as u8);
+        utf8.push(0b10000000 | ((ch >> 12) & 0b00111111) as u8);
+        utf8.push(0b10000000 | ((ch >> 6) & 0b00111111) as u8);
+        utf8.push(0b10000000 | (ch & 0b00111111) as u8);
+    }
+}
+
+fn push_utf16(ch: u32, utf16: &mut Vec<u16>) {
+    if ch <= 0xffff {
+        utf16.push(ch as u16);
+    } else {
+        let ch = ch - 0x10000;
+        utf16.push(0xd800 | (ch >> 10) as u16);
+        utf16.push(0xdc00 | (ch & 0x3ff) as u16);
+    }
+}
+
+// binary searches tables of sorted, inclusive code point ranges
+fn find_range<T: Copy>(table: &[(u32, u32, T)], ch: u32) -> Option<T> {
+    table.binary_search_by(|&(start, end, _)| if end < ch {
+            Ordering::Less
+        } else if start > ch {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        })
+        .ok()
+        .map(|i| table[i].2)
+}
+
+fn in_ranges(table: &[(u32, u32)], ch: u32) -> bool {
+    table.binary_search_by(|&(start, end)| if end < ch {
+            Ordering::Less
+        } else if start > ch {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        })
+        .is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     pub fn make_strings<S: Into<String>>(utf8: S) -> (Vec<u8>, Vec<u16>) {
@@ -86,6 +322,26 @@ mod tests {
 
             let (utf8, utf16) = make_strings("\u{0800}\u{ffff}");
             assert_eq!(utf8, to_utf8(&utf16[..]));
+
+            let (utf8, utf16) = make_strings("\u{10000}\u{10ffff}");
+            assert_eq!(utf8, to_utf8(&utf16[..]));
+        }
+
+        #[test]
+        fn emoji() {
+            let (utf8, utf16) = make_strings("Hello, 🦀! 👍🏽");
+            assert_eq!(utf8, to_utf8(&utf16[..]));
+            assert_eq!(utf8, try_to_utf8(&utf16[..]).unwrap());
+        }
+
+        #[test]
+        fn unpaired_surrogates() {
+            let utf16 = [0x61, 0xd83e, 0x62, 0xdd80, 0xd83e];
+            assert_eq!("a\u{fffd}b\u{fffd}\u{fffd}".as_bytes(), &to_utf8(&utf16)[..]);
+
+            let error = try_to_utf8(&utf16).unwrap_err();
+            assert_eq!("Unpaired surrogate 0xd83e at 1", error.to_string());
+            assert!(try_to_utf8(&[0xdd80]).is_err());
         }
     }
 
@@ -96,31 +352,266 @@ mod tests {
         #[test]
         fn ascii() {
             let (utf8, utf16) = make_strings("Hello, Rust!");
-            assert_eq!(utf16, to_utf16(&utf8[..]));
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
         }
 
         #[test]
         fn russian() {
             let (utf8, utf16) = make_strings("Привет, Раст!");
-            assert_eq!(utf16, to_utf16(&utf8[..]));
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
         }
 
         #[test]
         fn japanese() {
             let (utf8, utf16) = make_strings("こんにちは、さび");
-            assert_eq!(utf16, to_utf16(&utf8[..]));
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
         }
 
         #[test]
         fn bounds() {
             let (utf8, utf16) = make_strings("\u{0000}\u{007f}");
-            assert_eq!(utf16, to_utf16(&utf8[..]));
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
 
             let (utf8, utf16) = make_strings("\u{0080}\u{07ff}");
-            assert_eq!(utf16, to_utf16(&utf8[..]));
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
 
             let (utf8, utf16) = make_strings("\u{0800}\u{ffff}");
-            assert_eq!(utf16, to_utf16(&utf8[..]));
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
+
+            let (utf8, utf16) = make_strings("\u{10000}\u{10ffff}");
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
+        }
+
+        #[test]
+        fn emoji() {
+            let (utf8, utf16) = make_strings("Hello, 🦀! 👍🏽");
+            assert_eq!(utf16, to_utf16(&utf8[..]).unwrap());
+        }
+    }
+
+    mod validation {
+        use std::str;
+        use super::super::*;
+
+        quickcheck! {
+            fn random_bytes(utf8: Vec<u8>) -> bool {
+                let lossy: Vec<u16> = String::from_utf8_lossy(&utf8[..]).encode_utf16().collect();
+                let matches_std = match (to_utf16(&utf8[..]), str::from_utf8(&utf8[..])) {
+                    (Ok(utf16), Ok(text)) => utf16 == text.encode_utf16().collect::<Vec<_>>(),
+                    (Err(error), Err(std_error)) => {
+                        error.offset == std_error.valid_up_to() &&
+                        (error.kind == Utf8ErrorKind::Truncated) ==
+                        std_error.error_len().is_none() &&
+                        std_error.error_len().map_or(true, |length| length == error.length)
+                    }
+                    _ => false,
+                };
+                matches_std && to_utf16_lossy(&utf8[..]) == lossy
+            }
+        }
+
+        #[test]
+        fn error_kinds() {
+            let cases: [(&[u8], usize, Utf8ErrorKind, usize); 10] =
+                [(b"ab\x80", 2, Utf8ErrorKind::InvalidLeadByte, 1),
+                 (b"\xff", 0, Utf8ErrorKind::InvalidLeadByte, 1),
+                 (b"a\xc3(", 1, Utf8ErrorKind::InvalidContinuation, 1),
+                 (b"\xc0\xaf", 0, Utf8ErrorKind::Overlong, 1),
+                 (b"\xe0\x80\xaf", 0, Utf8ErrorKind::Overlong, 1),
+                 (b"\xf0\x82\x82\xac", 0, Utf8ErrorKind::Overlong, 1),
+                 (b"\xed\xa0\x80", 0, Utf8ErrorKind::Surrogate, 1),
+                 (b"\xf4\x90\x80\x80", 0, Utf8ErrorKind::OutOfRange, 1),
+                 (b"\xe2\x82", 0, Utf8ErrorKind::Truncated, 2),
+                 (b"\xf0\x9f\xa6\x28", 0, Utf8ErrorKind::InvalidContinuation, 3)];
+
+            for &(utf8, offset, kind, length) in &cases {
+                let expected = Utf8Error {
+                    offset: offset,
+                    kind: kind,
+                    length: length,
+                };
+                assert_eq!(Err(expected), to_utf16(utf8));
+            }
+        }
+
+        #[test]
+        fn lossy() {
+            let utf8 = b"a\xf0\x9f\xa6b\xc0\xafc\xe2\x82";
+            let utf16: Vec<u16> = "a\u{fffd}b\u{fffd}\u{fffd}c\u{fffd}".encode_utf16().collect();
+            assert_eq!(utf16, to_utf16_lossy(utf8));
+
+            let error = to_utf16(utf8).unwrap_err();
+            assert_eq!("Invalid UTF-8 continuation byte at 1", error.to_string());
+        }
+    }
+
+    mod utf32 {
+        use super::*;
+        use super::super::*;
+
+        #[test]
+        fn roundtrip() {
+            let (utf8, _) = make_strings("a\u{7ff}\u{ffff}🦀");
+            let utf32 = vec![0x61, 0x7ff, 0xffff, 0x1f980];
+            assert_eq!(utf32, to_utf32(&utf8[..]).unwrap());
+            assert_eq!(utf8, from_utf32(&utf32[..]).unwrap());
+        }
+
+        #[test]
+        fn invalid() {
+            assert_eq!(Utf8ErrorKind::Surrogate,
+                       to_utf32(b"a\xed\xa0\x80").unwrap_err().kind);
+            let error = from_utf32(&[0x61, 0x110000]).unwrap_err();
+            assert_eq!("Invalid code point 0x110000 at 1", error.to_string());
+            assert!(from_utf32(&[0xdc00]).is_err());
+        }
+    }
+
+    mod charsets {
+        use std::io::Cursor;
+        use super::super::*;
+
+        const TEXT: &'static str = "Привет, Раст! Ёё";
+
+        quickcheck! {
+            fn random_unicode_transcoding(text: String, chunk_size: usize) -> bool {
+                // a leading U+FEFF would be taken for a byte order mark
+                let text = format!("x{}", text);
+                let chunk_size = chunk_size % 7 + 1;
+                let unicode = &CHARSETS[..7];
+                unicode.iter().all(|&from| {
+                    unicode.iter().all(|&to| {
+                        let input = transcode(Charset::Utf8, from, text.as_bytes(), chunk_size);
+                        let output = transcode(from, to, &input[..], chunk_size);
+                        transcode(to, Charset::Utf8, &output[..], chunk_size) == text.as_bytes()
+                    })
+                })
+            }
+        }
+
+        #[test]
+        fn legacy() {
+            let cases: [(Charset, &[u8]); 3] =
+                [(Charset::Windows1251,
+                  b"\xcf\xf0\xe8\xe2\xe5\xf2, \xd0\xe0\xf1\xf2! \xa8\xb8"),
+                 (Charset::Koi8R, b"\xf0\xd2\xc9\xd7\xc5\xd4, \xf2\xc1\xd3\xd4! \xb3\xa3"),
+                 (Charset::Cp866, b"\x8f\xe0\xa8\xa2\xa5\xe2, \x90\xa0\xe1\xe2! \xf0\xf1")];
+
+            for &(charset, encoded) in &cases {
+                assert_eq!(TEXT.as_bytes(), &transcode(charset, Charset::Utf8, encoded, 3)[..]);
+                assert_eq!(encoded, &transcode(Charset::Utf8, charset, TEXT.as_bytes(), 3)[..]);
+            }
+
+            let latin1 = transcode(Charset::Latin1, Charset::Utf8, b"caf\xe9 \xff", 1);
+            assert_eq!("café ÿ".as_bytes(), &latin1[..]);
+        }
+
+        #[test]
+        fn bom() {
+            let mut transcoder = Transcoder::new(Charset::Utf8, Charset::Utf16Le);
+            transcoder.set_emit_bom(true);
+            let mut output = vec![];
+            transcoder.transcode(b"\xef\xbb\xbfhi", &mut output).unwrap();
+            transcoder.finish(&mut output).unwrap();
+            assert_eq!(b"\xff\xfeh\x00i\x00".to_vec(), output);
+
+            // a UTF-16LE file exported by a Windows tool, read as big-endian by default
+            let input = b"\xff\xfe\x1f\x04\x40\x04";
+            let mut transcoder = Transcoder::new(Charset::Utf16, Charset::Utf8);
+            let mut output = vec![];
+            transcoder.transcode(&input[..1], &mut output).unwrap();
+            transcoder.transcode(&input[1..], &mut output).unwrap();
+            transcoder.finish(&mut output).unwrap();
+            assert_eq!("Пр".as_bytes(), &output[..]);
+            assert_eq!(Charset::Utf16Le, transcoder.from());
+
+            assert_eq!(Some(Charset::Utf32Le), Charset::detect_bom(b"\xff\xfe\x00\x00"));
+            assert_eq!(Some(Charset::Utf16Le), Charset::detect_bom(b"\xff\xfe\x00a"));
+            assert_eq!(None, Charset::detect_bom(b"ab"));
+            assert_eq!(b"a".to_vec(), transcode(Charset::Utf16Le, Charset::Utf8, b"a\x00", 1));
+
+            // only unmarked charsets are sniffed
+            let input = b"\xff\xfe\x00\x00";
+            assert_eq!("\u{feff}\u{0}".as_bytes(),
+                       &transcode(Charset::Utf16Le, Charset::Utf8, input, 1)[..]);
+            assert_eq!("\u{0}".as_bytes(), &transcode(Charset::Utf16, Charset::Utf8, input, 1)[..]);
+            assert_eq!("\u{fffe}".as_bytes(),
+                       &transcode(Charset::Utf16Be, Charset::Utf8, &input[..2], 1)[..]);
+            assert_eq!(b"\xfe\xff\x00a".to_vec(),
+                       transcode(Charset::Utf8, Charset::Utf16, b"a", 1));
+        }
+
+        #[test]
+        fn errors() {
+            let error = transcode_result(Charset::Utf8, Charset::Koi8R, "ok 🦀".as_bytes(), false)
+                .unwrap_err();
+            assert_eq!("U+1F980 at 3 can't be encoded in KOI8-R", error.to_string());
+
+            let error = transcode_result(Charset::Utf16Le, Charset::Utf8, b"a\x00\x00\xdc", false)
+                .unwrap_err();
+            assert_eq!("Invalid UTF-16LE sequence at 2", error.to_string());
+            assert!(transcode_result(Charset::Utf8, Charset::Utf16Be, b"ab\xe2\x82", false)
+                .is_err());
+            assert!(transcode_result(Charset::Windows1251, Charset::Utf8, b"\x98", false)
+                .is_err());
+
+            let input = b"\xd0\x96\xf0\x9f\xa6\x80\xff";
+            let lossy = transcode_result(Charset::Utf8, Charset::Cp866, input, true);
+            assert_eq!(b"\x86??".to_vec(), lossy.unwrap());
+            let lossy = transcode_result(Charset::Utf8, Charset::Utf8, b"a\xe2\x82", true);
+            assert_eq!("a\u{fffd}".as_bytes(), &lossy.unwrap()[..]);
+        }
+
+        #[test]
+        fn names() {
+            for &charset in &CHARSETS {
+                assert_eq!(Some(charset), Charset::from_name(charset.name()));
+            }
+            assert_eq!(Some(Charset::Windows1251), Charset::from_name("cp1251"));
+            assert_eq!(Some(Charset::Utf16), Charset::from_name("utf_16"));
+            assert_eq!(Some(Charset::Utf16Be), Charset::from_name("UTF-16BE"));
+            assert_eq!(None, Charset::from_name("ebcdic"));
+        }
+
+        #[test]
+        fn copy() {
+            let input = TEXT.repeat(1000);
+            let mut transcoder = Transcoder::new(Charset::Utf8, Charset::Windows1251);
+            let mut output = vec![];
+            let read = transcoder.copy(&mut Cursor::new(input.as_bytes()), &mut output).unwrap();
+            assert_eq!(input.len() as u64, read);
+            assert_eq!(input.as_bytes(),
+                       &transcode(Charset::Windows1251, Charset::Utf8, &output[..], 100)[..]);
+        }
+
+        fn transcode(from: Charset, to: Charset, input: &[u8], chunk_size: usize) -> Vec<u8> {
+            let mut transcoder = Transcoder::new(from, to);
+            let mut output = vec![];
+            for chunk in input.chunks(chunk_size) {
+                transcoder.transcode(chunk, &mut output).unwrap();
+            }
+            transcoder.finish(&mut output).unwrap();
+            output
+        }
+
+        fn transcode_result(from: Charset,
+                            to: Charset,
+                            input: &[u8],
+                            lossy: bool)
+                            -> Result<Vec<u8>> {
+            let mut transcoder = Transcoder::new(from, to);
+            transcoder.set_lossy(lossy);
+            let mut output = vec![];
+            try!(transcoder.transcode(input, &mut output));
+            try!(transcoder.finish(&mut output));
+            Ok(output)
+        }
+    }
+
+    quickcheck! {
+        fn random_roundtrip(text: String) -> bool {
+            let (utf8, utf16) = make_strings(text);
+            super::to_utf8(&utf16[..]) == utf8 && super::to_utf16(&utf8[..]).unwrap() == utf16
         }
     }
 }
@@ -0,0 +1,250 @@
+use super::{REPLACEMENT_CHARACTER, Utf8ErrorKind, decode_utf16, decode_utf8, is_scalar_value,
+            push_utf16, push_utf8};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Charset {
+    Utf8,
+    // unmarked UTF-16 and UTF-32 are big-endian unless a byte order mark says otherwise
+    Utf16,
+    Utf16Be,
+    Utf16Le,
+    Utf32,
+    Utf32Be,
+    Utf32Le,
+    Latin1,
+    Windows1251,
+    Koi8R,
+    Cp866,
+}
+
+pub const CHARSETS: [Charset; 11] = [Charset::Utf8,
+                                     Charset::Utf16,
+                                     Charset::Utf16Be,
+                                     Charset::Utf16Le,
+                                     Charset::Utf32,
+                                     Charset::Utf32Be,
+                                     Charset::Utf32Le,
+                                     Charset::Latin1,
+                                     Charset::Windows1251,
+                                     Charset::Koi8R,
+                                     Charset::Cp866];
+
+impl Charset {
+    pub fn from_name(name: &str) -> Option<Charset> {
+        match name.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Some(Charset::Utf8),
+            "utf-16" => Some(Charset::Utf16),
+            "utf-16be" => Some(Charset::Utf16Be),
+            "utf-16le" => Some(Charset::Utf16Le),
+            "utf-32" => Some(Charset::Utf32),
+            "utf-32be" => Some(Charset::Utf32Be),
+            "utf-32le" => Some(Charset::Utf32Le),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Charset::Latin1),
+            "windows-1251" | "cp1251" => Some(Charset::Windows1251),
+            "koi8-r" | "koi8r" => Some(Charset::Koi8R),
+            "cp866" | "ibm866" => Some(Charset::Cp866),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Charset::Utf8 => "UTF-8",
+            Charset::Utf16 => "UTF-16",
+            Charset::Utf16Be => "UTF-16BE",
+            Charset::Utf16Le => "UTF-16LE",
+            Charset::Utf32 => "UTF-32",
+            Charset::Utf32Be => "UTF-32BE",
+            Charset::Utf32Le => "UTF-32LE",
+            Charset::Latin1 => "ISO-8859-1",
+            Charset::Windows1251 => "WINDOWS-1251",
+            Charset::Koi8R => "KOI8-R",
+            Charset::Cp866 => "CP866",
+        }
+    }
+
+    pub fn is_unicode(&self) -> bool {
+        !self.bom().is_empty()
+    }
+
+    pub fn bom(&self) -> &'static [u8] {
+        match *self {
+            Charset::Utf8 => b"\xef\xbb\xbf",
+            Charset::Utf16 | Charset::Utf16Be => b"\xfe\xff",
+            Charset::Utf16Le => b"\xff\xfe",
+            Charset::Utf32 | Charset::Utf32Be => b"\x00\x00\xfe\xff",
+            Charset::Utf32Le => b"\xff\xfe\x00\x00",
+            _ => b"",
+        }
+    }
+
+    // UTF-32LE is checked before UTF-16LE, whose byte order mark is its prefix
+    pub fn detect_bom(data: &[u8]) -> Option<Charset> {
+        [Charset::Utf32Le, Charset::Utf32Be, Charset::Utf8, Charset::Utf16Be, Charset::Utf16Le]
+            .iter()
+            .find(|charset| data.starts_with(charset.bom()))
+            .cloned()
+    }
+
+    // the charsets whose byte order mark may start an input labelled with this one
+    pub(super) fn marked_charsets(&self) -> &'static [Charset] {
+        match *self {
+            Charset::Utf8 => &[Charset::Utf8],
+            Charset::Utf16 => &[Charset::Utf16Be, Charset::Utf16Le],
+            Charset::Utf32 => &[Charset::Utf32Be, Charset::Utf32Le],
+            _ => &[],
+        }
+    }
+
+    fn table(&self) -> Option<&'static [u16; 128]> {
+        match *self {
+            Charset::Windows1251 => Some(&WINDOWS_1251_TABLE),
+            Charset::Koi8R => Some(&KOI8_R_TABLE),
+            Charset::Cp866 => Some(&CP866_TABLE),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Decoded {
+    Char(u32, usize),
+    Invalid(usize),
+    // more input is needed to decode the next character
+    Incomplete,
+}
+
+pub fn decode_char(charset: Charset, data: &[u8]) -> Decoded {
+    let unit_length = match charset {
+        Charset::Utf16 | Charset::Utf16Be | Charset::Utf16Le => 2,
+        Charset::Utf32 | Charset::Utf32Be | Charset::Utf32Le => 4,
+        _ => 1,
+    };
+    if data.len() < unit_length {
+        return Decoded::Incomplete;
+    }
+
+    match charset {
+        Charset::Utf8 => {
+            match decode_utf8(data) {
+                Ok((ch, length)) => Decoded::Char(ch, length),
+                Err((Utf8ErrorKind::Truncated, _)) => Decoded::Incomplete,
+                Err((_, length)) => Decoded::Invalid(length),
+            }
+        }
+        Charset::Utf16 | Charset::Utf16Be | Charset::Utf16Le => {
+            let unit = |i: usize| if charset != Charset::Utf16Le {
+                (data[i] as u16) << 8 | data[i + 1] as u16
+            } else {
+                (data[i + 1] as u16) << 8 | data[i] as u16
+            };
+            let first = unit(0);
+            match first {
+                0xd800...0xdbff if data.len() < 4 => Decoded::Incomplete,
+                0xd800...0xdbff => {
+                    match decode_utf16(&[first, unit(2)]) {
+                        (Some(ch), _) => Decoded::Char(ch, 4),
+                        (None, _) => Decoded::Invalid(2),
+                    }
+                }
+                0xdc00...0xdfff => Decoded::Invalid(2),
+                _ => Decoded::Char(first as u32, 2),
+            }
+        }
+        Charset::Utf32 | Charset::Utf32Be | Charset::Utf32Le => {
+            let ch = if charset != Charset::Utf32Le {
+                data[..4].iter().fold(0, |ch, &byte| ch << 8 | byte as u32)
+            } else {
+                data[..4].iter().rev().fold(0, |ch, &byte| ch << 8 | byte as u32)
+            };
+            if is_scalar_value(ch) {
+                Decoded::Char(ch, 4)
+            } else {
+                Decoded::Invalid(4)
+            }
+        }
+        Charset::Latin1 => Decoded::Char(data[0] as u32, 1),
+        _ => {
+            let byte = data[0];
+            if byte < 0x80 {
+                return Decoded::Char(byte as u32, 1);
+            }
+            match charset.table().unwrap()[byte as usize - 0x80] as u32 {
+                REPLACEMENT_CHARACTER => Decoded::Invalid(1),
+                ch => Decoded::Char(ch, 1),
+            }
+        }
+    }
+}
+
+// returns false if the character can't be represented in the charset
+pub fn encode_char(charset: Charset, ch: u32, output: &mut Vec<u8>) -> bool {
+    match charset {
+        Charset::Utf8 => push_utf8(ch, output),
+        Charset::Utf16 | Charset::Utf16Be | Charset::Utf16Le => {
+            let mut units = Vec::with_capacity(2);
+            push_utf16(ch, &mut units);
+            for unit in units {
+                let bytes = [(unit >> 8) as u8, unit as u8];
+                if charset != Charset::Utf16Le {
+                    output.extend_from_slice(&bytes);
+                } else {
+                    output.extend(bytes.iter().rev());
+                }
+            }
+        }
+        Charset::Utf32 | Charset::Utf32Be => {
+            output.extend((0..4).rev().map(|i| (ch >> (i * 8)) as u8))
+        }
+        Charset::Utf32Le => output.extend((0..4).map(|i| (ch >> (i * 8)) as u8)),
+        Charset::Latin1 if ch <= 0xff => output.push(ch as u8),
+        Charset::Latin1 => return false,
+        _ if ch < 0x80 => output.push(ch as u8),
+        _ => {
+            match charset.table().unwrap().iter().position(|&mapped| mapped as u32 == ch) {
+                Some(i) if ch != REPLACEMENT_CHARACTER => output.push(0x80 + i as u8),
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+static WINDOWS_1251_TABLE: [u16; 128] =
+    [0x0402, 0x0403, 0x201a, 0x0453, 0x201e, 0x2026, 0x2020, 0x2021, 0x20ac, 0x2030, 0x0409, 0x2039,
+     0x040a, 0x040c, 0x040b, 0x040f, 0x0452, 0x2018, 0x2019, 0x201c, 0x201d, 0x2022, 0x2013, 0x2014,
+     0xfffd, 0x2122, 0x0459, 0x203a, 0x045a, 0x045c, 0x045b, 0x045f, 0x00a0, 0x040e, 0x045e, 0x0408,
+     0x00a4, 0x0490, 0x00a6, 0x00a7, 0x0401, 0x00a9, 0x0404, 0x00ab, 0x00ac, 0x00ad, 0x00ae, 0x0407,
+     0x00b0, 0x00b1, 0x0406, 0x0456, 0x0491, 0x00b5, 0x00b6, 0x00b7, 0x0451, 0x2116, 0x0454, 0x00bb,
+     0x0458, 0x0405, 0x0455, 0x0457, 0x0410, 0x0411, 0x0412, 0x0413, 0x0414, 0x0415, 0x0416, 0x0417,
+     0x0418, 0x0419, 0x041a, 0x041b, 0x041c, 0x041d, 0x041e, 0x041f, 0x0420, 0x0421, 0x0422, 0x0423,
+     0x0424, 0x0425, 0x0426, 0x0427, 0x0428, 0x0429, 0x042a, 0x042b, 0x042c, 0x042d, 0x042e, 0x042f,
+     0x0430, 0x0431, 0x0432, 0x0433, 0x0434, 0x0435, 0x0436, 0x0437, 0x0438, 0x0439, 0x043a, 0x043b,
+     0x043c, 0x043d, 0x043e, 0x043f, 0x0440, 0x0441, 0x0442, 0x0443, 0x0444, 0x0445, 0x0446, 0x0447,
+     0x0448, 0x0449, 0x044a, 0x044b, 0x044c, 0x044d, 0x044e, 0x044f];
+
+static KOI8_R_TABLE: [u16; 128] =
+    [0x2500, 0x2502, 0x250c, 0x2510, 0x2514, 0x2518, 0x251c, 0x2524, 0x252c, 0x2534, 0x253c, 0x2580,
+     0x2584, 0x2588, 0x258c, 0x2590, 0x2591, 0x2592, 0x2593, 0x2320, 0x25a0, 0x2219, 0x221a, 0x2248,
+     0x2264, 0x2265, 0x00a0, 0x2321, 0x00b0, 0x00b2, 0x00b7, 0x00f7, 0x2550, 0x2551, 0x2552, 0x0451,
+     0x2553, 0x2554, 0x2555, 0x2556, 0x2557, 0x2558, 0x2559, 0x255a, 0x255b, 0x255c, 0x255d, 0x255e,
+     0x255f, 0x2560, 0x2561, 0x0401, 0x2562, 0x2563, 0x2564, 0x2565, 0x2566, 0x2567, 0x2568, 0x2569,
+     0x256a, 0x256b, 0x256c, 0x00a9, 0x044e, 0x0430, 0x0431, 0x0446, 0x0434, 0x0435, 0x0444, 0x0433,
+     0x0445, 0x0438, 0x0439, 0x043a, 0x043b, 0x043c, 0x043d, 0x043e, 0x043f, 0x044f, 0x0440, 0x0441,
+     0x0442, 0x0443, 0x0436, 0x0432, 0x044c, 0x044b, 0x0437, 0x0448, 0x044d, 0x0449, 0x0447, 0x044a,
+     0x042e, 0x0410, 0x0411, 0x0426, 0x0414, 0x0415, 0x0424, 0x0413, 0x0425, 0x0418, 0x0419, 0x041a,
+     0x041b, 0x041c, 0x041d, 0x041e, 0x041f, 0x042f, 0x0420, 0x0421, 0x0422, 0x0423, 0x0416, 0x0412,
+     0x042c, 0x042b, 0x0417, 0x0428, 0x042d, 0x0429, 0x0427, 0x042a];
+
+static CP866_TABLE: [u16; 128] =
+    [0x0410, 0x0411, 0x0412, 0x0413, 0x0414, 0x0415, 0x0416, 0x0417, 0x0418, 0x0419, 0x041a, 0x041b,
+     0x041c, 0x041d, 0x041e, 0x041f, 0x0420, 0x0421, 0x0422, 0x0423, 0x0424, 0x0425, 0x0426, 0x0427,
+     0x0428, 0x0429, 0x042a, 0x042b, 0x042c, 0x042d, 0x042e, 0x042f, 0x0430, 0x0431, 0x0432, 0x0433,
+     0x0434, 0x0435, 0x0436, 0x0437, 0x0438, 0x0439, 0x043a, 0x043b, 0x043c, 0x043d, 0x043e, 0x043f,
+     0x2591, 0x2592, 0x2593, 0x2502, 0x2524, 0x2561, 0x2562, 0x2556, 0x2555, 0x2563, 0x2551, 0x2557,
+     0x255d, 0x255c, 0x255b, 0x2510, 0x2514, 0x2534, 0x252c, 0x251c, 0x2500, 0x253c, 0x255e, 0x255f,
+     0x255a, 0x2554, 0x2569, 0x2566, 0x2560, 0x2550, 0x256c, 0x2567, 0x2568, 0x2564, 0x2565, 0x2559,
+     0x2558, 0x2552, 0x2553, 0x256b, 0x256a, 0x2518, 0x250c, 0x2588, 0x2584, 0x258c, 0x2590, 0x2580,
+     0x0440, 0x0441, 0x0442, 0x0443, 0x0444, 0x0445, 0x0446, 0x0447, 0x0448, 0x0449, 0x044a, 0x044b,
+     0x044c, 0x044d, 0x044e, 0x044f, 0x0401, 0x0451, 0x0404, 0x0454, 0x0407, 0x0457, 0x040e, 0x045e,
+     0x00b0, 0x2219, 0x00b7, 0x221a, 0x2116, 0x00a4, 0x25a0, 0x00a0];
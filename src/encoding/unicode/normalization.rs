@@ -0,0 +1,168 @@
+use super::find_range;
+use super::tables::{CANONICAL_COMBINING_CLASSES, CANONICAL_COMPOSITIONS,
+                    CANONICAL_DECOMPOSITIONS};
+use std::char;
+
+// Hangul syllables are composed of a leading consonant, a vowel and an optional trailing one
+const S_BASE: u32 = 0xac00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11a7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+pub fn canonical_combining_class(ch: char) -> u8 {
+    find_range(CANONICAL_COMBINING_CLASSES, ch as u32).unwrap_or(0)
+}
+
+pub fn nfd(text: &str) -> String {
+    decompose(text).into_iter().collect()
+}
+
+pub fn nfc(text: &str) -> String {
+    compose(decompose(text)).into_iter().collect()
+}
+
+fn decompose(text: &str) -> Vec<char> {
+    let mut result = Vec::with_capacity(text.len());
+
+    for ch in text.chars() {
+        let code_point = ch as u32;
+        if code_point >= S_BASE && code_point < S_BASE + S_COUNT {
+            let index = code_point - S_BASE;
+            result.push(to_char(L_BASE + index / N_COUNT));
+            result.push(to_char(V_BASE + index % N_COUNT / T_COUNT));
+            if index % T_COUNT != 0 {
+                result.push(to_char(T_BASE + index % T_COUNT));
+            }
+            continue;
+        }
+
+        match CANONICAL_DECOMPOSITIONS.binary_search_by_key(&code_point, |&(ch, _)| ch) {
+            Ok(i) => result.extend(CANONICAL_DECOMPOSITIONS[i].1.iter().map(|&ch| to_char(ch))),
+            Err(_) => result.push(ch),
+        }
+    }
+
+    // canonical ordering: a stable sort of every run of combining marks by their class
+    for i in 1..result.len() {
+        let mut j = i;
+        while j > 0 {
+            let class = canonical_combining_class(result[j]);
+            if class == 0 || canonical_combining_class(result[j - 1]) <= class {
+                break;
+            }
+            result.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+
+    result
+}
+
+fn compose(chars: Vec<char>) -> Vec<char> {
+    let mut result: Vec<char> = Vec::with_capacity(chars.len());
+    let mut starter = None;
+    // the class of the last character after the starter, the largest one as they are ordered
+    let mut last_class = 0;
+
+    for ch in chars {
+        let class = canonical_combining_class(ch);
+
+        if let Some(starter) = starter {
+            // a character is blocked from the starter by one of the same or zero class
+            let adjacent = starter + 1 == result.len();
+            if adjacent || last_class != 0 && last_class < class {
+                if let Some(composed) = compose_pair(result[starter], ch) {
+                    result[starter] = composed;
+                    continue;
+                }
+            }
+        }
+
+        if class == 0 {
+            starter = Some(result.len());
+        }
+        last_class = class;
+        result.push(ch);
+    }
+
+    result
+}
+
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (first, second) = (first as u32, second as u32);
+
+    if first >= L_BASE && first < L_BASE + L_COUNT && second >= V_BASE &&
+       second < V_BASE + V_COUNT {
+        let index = (first - L_BASE) * N_COUNT + (second - V_BASE) * T_COUNT;
+        return Some(to_char(S_BASE + index));
+    }
+    if first >= S_BASE && first < S_BASE + S_COUNT && (first - S_BASE) % T_COUNT == 0 &&
+       second > T_BASE && second < T_BASE + T_COUNT {
+        return Some(to_char(first + second - T_BASE));
+    }
+
+    CANONICAL_COMPOSITIONS.binary_search_by_key(&(first, second), |&(pair, _)| pair)
+        .ok()
+        .map(|i| to_char(CANONICAL_COMPOSITIONS[i].1))
+}
+
+// the tables only hold valid scalar values
+fn to_char(code_point: u32) -> char {
+    char::from_u32(code_point).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn random_idempotent(text: String) -> bool {
+            let decomposed = nfd(&text);
+            let composed = nfc(&text);
+            nfd(&decomposed) == decomposed && nfc(&composed) == composed &&
+            nfc(&decomposed) == composed && nfd(&composed) == decomposed
+        }
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!("e\u{301}", nfd("é"));
+        assert_eq!("é", nfc("e\u{301}"));
+        assert_eq!("Å", nfc("A\u{30a}"));
+        // the angstrom sign is a singleton decomposition, which never recomposes
+        assert_eq!("Å", nfc("\u{212b}"));
+        assert_eq!("plain ascii", nfc("plain ascii"));
+    }
+
+    #[test]
+    fn canonical_ordering() {
+        // dot below (220) sorts before circumflex (230)
+        assert_eq!("q\u{323}\u{302}", nfd("q\u{302}\u{323}"));
+        assert_eq!("\u{1ec7}", nfc("e\u{302}\u{323}"));
+        assert_eq!("\u{1ec7}", nfc("e\u{323}\u{302}"));
+        assert_eq!(220, canonical_combining_class('\u{323}'));
+        assert_eq!(0, canonical_combining_class('a'));
+    }
+
+    #[test]
+    fn blocking() {
+        // the second acute is blocked by the first one
+        assert_eq!("\u{e1}\u{301}", nfc("a\u{301}\u{301}"));
+        // a starter between the base and the mark blocks composition
+        assert_eq!("ab\u{301}", nfc("ab\u{301}"));
+        assert_eq!("a\u{1d165}\u{301}", nfd("a\u{1d165}\u{301}"));
+    }
+
+    #[test]
+    fn hangul() {
+        assert_eq!("\u{1112}\u{1161}\u{11ab}", nfd("한"));
+        assert_eq!("한", nfc("\u{1112}\u{1161}\u{11ab}"));
+        assert_eq!("하", nfc("\u{1112}\u{1161}"));
+        assert_eq!("한국어", nfc(&nfd("한국어")));
+    }
+}
@@ -0,0 +1,212 @@
+use super::{find_range, in_ranges};
+use super::tables::{EXTENDED_PICTOGRAPHIC, GRAPHEME_BREAKS, WIDE, ZERO_WIDTH};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GraphemeBreak {
+    Other,
+    Cr,
+    Lf,
+    Control,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    Prepend,
+    SpacingMark,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+}
+
+pub fn grapheme_break(ch: char) -> GraphemeBreak {
+    let code_point = ch as u32;
+    // precomposed Hangul syllables alternate between LV and LVT
+    if code_point >= 0xac00 && code_point <= 0xd7a3 {
+        return if (code_point - 0xac00) % 28 == 0 {
+            GraphemeBreak::Lv
+        } else {
+            GraphemeBreak::Lvt
+        };
+    }
+    find_range(GRAPHEME_BREAKS, code_point).unwrap_or(GraphemeBreak::Other)
+}
+
+// extended grapheme clusters, as defined by UAX #29
+pub struct Graphemes<'a> {
+    text: &'a str,
+}
+
+pub fn graphemes(text: &str) -> Graphemes {
+    Graphemes { text: text }
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.text.char_indices();
+        let first = match chars.next() {
+            Some((_, ch)) => ch,
+            None => return None,
+        };
+
+        let mut state = ClusterState::new(first);
+        let mut end = self.text.len();
+        for (i, ch) in chars {
+            if state.is_boundary(ch) {
+                end = i;
+                break;
+            }
+        }
+
+        let (grapheme, rest) = self.text.split_at(end);
+        self.text = rest;
+        Some(grapheme)
+    }
+}
+
+struct ClusterState {
+    previous: GraphemeBreak,
+    // consecutive regional indicators ending with the previous character
+    regional_indicators: usize,
+    // the cluster ends with an extended pictographic character followed by Extend*
+    pictographic: bool,
+    // the cluster ends with an extended pictographic character followed by Extend* ZWJ
+    pictographic_zwj: bool,
+}
+
+impl ClusterState {
+    fn new(first: char) -> Self {
+        let mut state = ClusterState {
+            previous: GraphemeBreak::Other,
+            regional_indicators: 0,
+            pictographic: false,
+            pictographic_zwj: false,
+        };
+        state.push(first, grapheme_break(first));
+        state
+    }
+
+    fn is_boundary(&mut self, ch: char) -> bool {
+        use self::GraphemeBreak::*;
+
+        let current = grapheme_break(ch);
+        let boundary = match (self.previous, current) {
+            (Cr, Lf) => false,
+            (Cr, _) | (Lf, _) | (Control, _) | (_, Cr) | (_, Lf) | (_, Control) => true,
+            (L, L) | (L, V) | (L, Lv) | (L, Lvt) => false,
+            (Lv, V) | (Lv, T) | (V, V) | (V, T) => false,
+            (Lvt, T) | (T, T) => false,
+            (_, Extend) | (_, Zwj) | (_, SpacingMark) | (Prepend, _) => false,
+            (Zwj, _) if self.pictographic_zwj && is_extended_pictographic(ch) => false,
+            (RegionalIndicator, RegionalIndicator) => self.regional_indicators % 2 == 0,
+            _ => true,
+        };
+
+        self.push(ch, current);
+        boundary
+    }
+
+    fn push(&mut self, ch: char, current: GraphemeBreak) {
+        self.pictographic_zwj = current == GraphemeBreak::Zwj && self.pictographic;
+        self.pictographic = is_extended_pictographic(ch) ||
+                            self.pictographic && current == GraphemeBreak::Extend;
+        self.regional_indicators = if current == GraphemeBreak::RegionalIndicator {
+            self.regional_indicators + 1
+        } else {
+            0
+        };
+        self.previous = current;
+    }
+}
+
+fn is_extended_pictographic(ch: char) -> bool {
+    in_ranges(EXTENDED_PICTOGRAPHIC, ch as u32)
+}
+
+// the number of terminal columns: 2 for East Asian wide characters, 0 for combining marks,
+// format and control characters
+pub fn char_width(ch: char) -> usize {
+    let code_point = ch as u32;
+    if code_point < 0x20 || code_point >= 0x7f && code_point < 0xa0 ||
+       in_ranges(ZERO_WIDTH, code_point) {
+        0
+    } else if in_ranges(WIDE, code_point) {
+        2
+    } else {
+        1
+    }
+}
+
+// a cluster is as wide as its widest character, or 2 if an emoji presentation is requested
+pub fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme.contains('\u{fe0f}') {
+        return 2;
+    }
+    grapheme.chars().map(char_width).max().unwrap_or(0)
+}
+
+pub fn width(text: &str) -> usize {
+    graphemes(text).map(grapheme_width).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    quickcheck! {
+        fn random_concatenation(text: String) -> bool {
+            graphemes(&text).collect::<String>() == text &&
+            graphemes(&text).all(|grapheme| !grapheme.is_empty())
+        }
+    }
+
+    #[test]
+    fn combining() {
+        assert_eq!(vec!["e\u{301}", "x"], split("e\u{301}x"));
+        assert_eq!(vec!["\r\n", "a", "\n", "\u{301}"], split("\r\na\n\u{301}"));
+        assert_eq!(vec!["\u{915}\u{94d}", "\u{937}\u{93f}"], split("\u{915}\u{94d}\u{937}\u{93f}"));
+        assert_eq!(vec!["\u{600}1"], split("\u{600}1"));
+    }
+
+    #[test]
+    fn hangul() {
+        assert_eq!(vec!["\u{1112}\u{1161}\u{11ab}", "국"], split("\u{1112}\u{1161}\u{11ab}국"));
+        assert_eq!(GraphemeBreak::Lv, grapheme_break('하'));
+        assert_eq!(GraphemeBreak::Lvt, grapheme_break('한'));
+    }
+
+    #[test]
+    fn emoji() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(vec![family, "!"], split(&format!("{}!", family)));
+        assert_eq!(vec!["👍🏽", "a\u{200d}", "\u{1f469}"], split("👍🏽a\u{200d}\u{1f469}"));
+        // regional indicators pair up into flags
+        let flags = "\u{1f1fa}\u{1f1e6}\u{1f1fa}\u{1f1f8}\u{1f1ea}";
+        assert_eq!(vec!["\u{1f1fa}\u{1f1e6}", "\u{1f1fa}\u{1f1f8}", "\u{1f1ea}"], split(flags));
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(1, char_width('a'));
+        assert_eq!(2, char_width('漢'));
+        assert_eq!(2, char_width('Ａ'));
+        assert_eq!(1, char_width('ｱ'));
+        assert_eq!(0, char_width('\u{301}'));
+        assert_eq!(0, char_width('\u{200b}'));
+        assert_eq!(1, char_width('\u{ad}'));
+        assert_eq!(0, char_width('\t'));
+
+        assert_eq!(5, width("e\u{301}tude"));
+        assert_eq!(10, width("こんにちは"));
+        assert_eq!(2, width("\u{1112}\u{1161}\u{11ab}"));
+        assert_eq!(2, width("\u{2764}\u{fe0f}"));
+        assert_eq!(2, width("👍🏽"));
+        assert_eq!(0, width(""));
+    }
+
+    fn split(text: &str) -> Vec<&str> {
+        graphemes(text).collect()
+    }
+}
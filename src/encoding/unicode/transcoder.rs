@@ -0,0 +1,145 @@
+use super::REPLACEMENT_CHARACTER;
+use super::charsets::{Charset, Decoded, decode_char, encode_char};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+const TRANSCODER_BUFFER_SIZE: usize = 8192;
+
+// converts a byte stream between charsets, keeping incomplete characters between calls
+pub struct Transcoder {
+    from: Charset,
+    to: Charset,
+    lossy: bool,
+    emit_bom: bool,
+    started: bool,
+    pending: Vec<u8>,
+    offset: u64,
+}
+
+impl Transcoder {
+    pub fn new(from: Charset, to: Charset) -> Self {
+        Transcoder {
+            from: from,
+            to: to,
+            lossy: false,
+            emit_bom: false,
+            started: false,
+            pending: vec![],
+            offset: 0,
+        }
+    }
+
+    // replaces invalid input with U+FFFD, and characters missing in the target with '?'
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    // starts a Unicode output with a byte order mark, unmarked UTF-16 and UTF-32 always have one
+    pub fn set_emit_bom(&mut self, emit_bom: bool) {
+        self.emit_bom = emit_bom;
+    }
+
+    // the source charset; for UTF-8 and unmarked UTF-16 and UTF-32 a byte order mark
+    // at the start of the input is skipped and picks the byte order
+    pub fn from(&self) -> Charset {
+        self.from
+    }
+
+    pub fn to(&self) -> Charset {
+        self.to
+    }
+
+    pub fn transcode(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<()> {
+        self.pending.extend_from_slice(input);
+        self.process(output, false)
+    }
+
+    pub fn finish(&mut self, output: &mut Vec<u8>) -> Result<()> {
+        self.process(output, true)
+    }
+
+    // returns the number of bytes read
+    pub fn copy(&mut self, input: &mut Read, output: &mut Write) -> Result<u64> {
+        let mut buffer = vec![0; TRANSCODER_BUFFER_SIZE];
+        let mut transcoded = Vec::with_capacity(TRANSCODER_BUFFER_SIZE * 4);
+        let mut total = 0;
+
+        loop {
+            let length = try!(input.read(&mut buffer[..]));
+            transcoded.clear();
+            if length == 0 {
+                try!(self.finish(&mut transcoded));
+                try!(output.write_all(&transcoded[..]));
+                return Ok(total);
+            }
+            try!(self.transcode(&buffer[..length], &mut transcoded));
+            try!(output.write_all(&transcoded[..]));
+            total += length as u64;
+        }
+    }
+
+    fn process(&mut self, output: &mut Vec<u8>, last: bool) -> Result<()> {
+        if !self.started {
+            // the longest byte order mark is 4 bytes
+            if self.from.is_unicode() && self.pending.len() < 4 && !last {
+                return Ok(());
+            }
+            self.start(output);
+        }
+
+        let mut i = 0;
+        while i < self.pending.len() {
+            let decoded = match decode_char(self.from, &self.pending[i..]) {
+                Decoded::Incomplete if last => Decoded::Invalid(self.pending.len() - i),
+                Decoded::Incomplete => break,
+                decoded => decoded,
+            };
+
+            let (ch, length) = match decoded {
+                Decoded::Char(ch, length) => (ch, length),
+                Decoded::Invalid(length) if self.lossy => (REPLACEMENT_CHARACTER, length),
+                _ => {
+                    let message = format!("Invalid {} sequence at {}",
+                                          self.from.name(),
+                                          self.offset + i as u64);
+                    return Err(Error::new(ErrorKind::InvalidData, message));
+                }
+            };
+
+            if !encode_char(self.to, ch, output) {
+                if !self.lossy {
+                    let message = format!("U+{:04X} at {} can't be encoded in {}",
+                                          ch,
+                                          self.offset + i as u64,
+                                          self.to.name());
+                    return Err(Error::new(ErrorKind::InvalidData, message));
+                }
+                output.push(b'?');
+            }
+            i += length;
+        }
+
+        let _ = self.pending.drain(..i);
+        self.offset += i as u64;
+        Ok(())
+    }
+
+    fn start(&mut self, output: &mut Vec<u8>) {
+        self.started = true;
+
+        let marked = self.from
+            .marked_charsets()
+            .iter()
+            .find(|charset| self.pending.starts_with(charset.bom()))
+            .cloned();
+        if let Some(charset) = marked {
+            let length = charset.bom().len();
+            let _ = self.pending.drain(..length);
+            self.offset += length as u64;
+            self.from = charset;
+        }
+
+        if self.emit_bom || self.to == Charset::Utf16 || self.to == Charset::Utf32 {
+            output.extend_from_slice(self.to.bom());
+        }
+    }
+}
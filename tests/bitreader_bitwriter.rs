@@ -5,6 +5,7 @@ extern crate rand;
 extern crate rust_experiments;
 
 use rand::Rng;
+use rust_experiments::encoding::bitorder::BitOrder;
 use rust_experiments::encoding::bitreader::BitReader;
 use rust_experiments::encoding::bitwriter::BitWriter;
 use std::collections::hash_map::HashMap;
@@ -40,6 +41,54 @@ quickcheck! {
         true
     }
 
+    fn simple_msb_first(input: Vec<u8>) -> bool {
+        let input_clone = input.clone();
+
+        let mut reader = BitReader::with_bit_order(Cursor::new(input), BitOrder::MsbFirst);
+        let mut writer = BitWriter::with_bit_order(vec![], BitOrder::MsbFirst);
+
+        loop {
+            if let Ok(long) = reader.read_u64() {
+                writer.write_u64(long).unwrap();
+            }
+
+            if let Ok(byte) = reader.read_u8() {
+                writer.write_u8(byte).unwrap();
+            }
+
+            match reader.read_bit() {
+                Ok(bit) => writer.write_bit(bit).unwrap(),
+                Err(_) => break,
+            }
+        }
+
+        writer.flush().unwrap();
+
+        let input_slice = &input_clone[..];
+        assert_eq!(input_slice, writer.get_ref().as_slice());
+
+        true
+    }
+
+    fn random_widths(xs: Vec<(u64, u8)>) -> bool {
+        copy_bits(&xs[..], BitOrder::LsbFirst) && copy_bits(&xs[..], BitOrder::MsbFirst)
+    }
+
+    fn random_signed_widths(xs: Vec<(i64, u8)>) -> bool {
+        copy_signed_bits(&xs[..], BitOrder::LsbFirst) &&
+        copy_signed_bits(&xs[..], BitOrder::MsbFirst)
+    }
+
+    fn random_byte_orders(xs: Vec<(u16, u32, u64, f64)>) -> bool {
+        copy_byte_orders(&xs[..], BitOrder::LsbFirst) &&
+        copy_byte_orders(&xs[..], BitOrder::MsbFirst)
+    }
+
+    fn random_variable_length(xs: Vec<(u64, i64, u8)>) -> bool {
+        copy_variable_length(&xs[..], BitOrder::LsbFirst) &&
+        copy_variable_length(&xs[..], BitOrder::MsbFirst)
+    }
+
     fn random_positions(xs: Vec<bool>) -> bool {
         let mut writer = BitWriter::new(vec![]);
 
@@ -71,3 +120,97 @@ quickcheck! {
         true
     }
 }
+
+fn copy_bits(xs: &[(u64, u8)], bit_order: BitOrder) -> bool {
+    let values = xs.iter()
+        .map(|&(value, length)| {
+            let length = length % 65;
+            let value = if length == 64 { value } else { value & ((1 << length) - 1) };
+            (value, length)
+        })
+        .collect::<Vec<(u64, u8)>>();
+
+    let mut writer = BitWriter::with_bit_order(vec![], bit_order);
+    for &(value, length) in &values {
+        writer.write_bits(value, length).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut reader = BitReader::with_bit_order(Cursor::new(writer.get_ref().as_slice()), bit_order);
+    values.iter().all(|&(value, length)| {
+        reader.peek_bits(length).unwrap() == value && reader.read_bits(length).unwrap() == value
+    })
+}
+
+fn copy_signed_bits(xs: &[(i64, u8)], bit_order: BitOrder) -> bool {
+    let values = xs.iter()
+        .map(|&(value, length)| {
+            let length = length % 64 + 1;
+            let shift = 64 - length as u32;
+            ((value << shift) >> shift, length)
+        })
+        .collect::<Vec<(i64, u8)>>();
+
+    let mut writer = BitWriter::with_bit_order(vec![], bit_order);
+    for &(value, length) in &values {
+        writer.write_signed_bits(value, length).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut reader = BitReader::with_bit_order(Cursor::new(writer.get_ref().as_slice()), bit_order);
+    values.iter().all(|&(value, length)| reader.read_signed_bits(length).unwrap() == value)
+}
+
+fn copy_byte_orders(xs: &[(u16, u32, u64, f64)], bit_order: BitOrder) -> bool {
+    let mut writer = BitWriter::with_bit_order(vec![], bit_order);
+    writer.write_bit(true).unwrap();
+    for &(a, b, c, d) in xs {
+        writer.write_u16_le(a).unwrap();
+        writer.write_u32_be(b).unwrap();
+        writer.write_u64_le(c).unwrap();
+        writer.write_f64_be(d).unwrap();
+        writer.write_f32_le(d as f32).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut reader = BitReader::with_bit_order(Cursor::new(writer.get_ref().as_slice()), bit_order);
+    reader.read_bit().unwrap() &&
+    xs.iter().all(|&(a, b, c, d)| {
+        reader.read_u16_le().unwrap() == a && reader.read_u32_be().unwrap() == b &&
+        reader.read_u64_le().unwrap() == c &&
+        reader.read_f64_be().unwrap().to_bits() == d.to_bits() &&
+        reader.read_f32_le().unwrap().to_bits() == (d as f32).to_bits()
+    })
+}
+
+fn copy_variable_length(xs: &[(u64, i64, u8)], bit_order: BitOrder) -> bool {
+    // rice codes of large values are long, keep only a few high bits above k
+    let values = xs.iter()
+        .map(|&(value, signed, k)| {
+            let k = k % 64;
+            let rice_value = value & ((1 << k) - 1 | (0xff << k));
+            (value, signed, k, rice_value)
+        })
+        .collect::<Vec<(u64, i64, u8, u64)>>();
+
+    let mut writer = BitWriter::with_bit_order(vec![], bit_order);
+    for &(value, signed, k, rice_value) in &values {
+        writer.write_leb128(value).unwrap();
+        writer.write_signed_leb128(signed).unwrap();
+        writer.write_elias_gamma(value | 1).unwrap();
+        writer.write_elias_delta(value | 1).unwrap();
+        writer.write_rice(rice_value, k).unwrap();
+        writer.write_exp_golomb(value >> 1, k).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut reader = BitReader::with_bit_order(Cursor::new(writer.get_ref().as_slice()), bit_order);
+    values.iter().all(|&(value, signed, k, rice_value)| {
+        reader.read_leb128().unwrap() == value &&
+        reader.read_signed_leb128().unwrap() == signed &&
+        reader.read_elias_gamma().unwrap() == value | 1 &&
+        reader.read_elias_delta().unwrap() == value | 1 &&
+        reader.read_rice(k).unwrap() == rice_value &&
+        reader.read_exp_golomb(k).unwrap() == value >> 1
+    })
+}